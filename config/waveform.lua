@@ -1,7 +1,14 @@
 -- 波形解析配置脚本
---协议：AA + 数据长度(通道号 + 数据长度) + 通道号（0-9，大于9不绘制曲线）+ 4字节数据 + 4字节数据 + ...
+--协议：AA + 数据长度(通道号 + 数据长度) + 通道号 + 4字节数据 + 4字节数据 + ...
 --如（DATA_TYPE = "int" 通道1，数值：305419896, -305419896） : AA 09 01 12 34 56 78 ED CB A9 88  // 长度9 = 1(通道号) + 8(两组数据)
 --如（DATA_TYPE = "float" 通道1，数值：10.0, -10.0） : AA 09 03 00 00 20 41 00 00 20 C1  // 长度9 = 1(通道号) + 8(两组数据)
+--
+-- parse_waveform 的返回值可以是：
+--   {channel = n, points = {y1, y2, ...}}               一个通道多个数据点
+--   {ch = n, y = 1.5, x = 100}                          单个数据点，x 或设备时间戳 t 可选
+--   {t = 12.5, {ch = 0, y = ax}, {ch = 1, y = ay}, ...}  一帧多个通道(如IMU六轴)，外层 t/x 作用于各通道
+--   各通道可附带 name = "accel_x", unit = "g" 作为通道名称和单位
+--   任意格式都可以附加 fields = {temp = 25.0, mode = "run"}，显示在波形窗口顶部
 -- 用户配置区
 FRAME_LENGTH = 11  -- 帧长度
 BYTES_PER_POINT = 4  -- 每个数据点的字节数
@@ -67,4 +74,32 @@ function parse_waveform(data)
     else
         return nil
     end
-end
\ No newline at end of file
+end
+
+-- 脚本API示例：自动应答和握手(取消注释即可使用)
+-- on_receive(function(bytes)
+--     -- 收到轮询帧 55 01 时回复 ACK
+--     if #bytes >= 2 and bytes[1] == 0x55 and bytes[2] == 0x01 then
+--         send_hex("55 81")
+--         log("收到轮询, 已应答")
+--     end
+-- end)
+--
+-- -- 每秒发送一次心跳，并把通道0的最新值显示在状态栏
+-- set_timer(1000, function()
+--     send({0x55, 0x00})
+--     local value = plot_value(0)
+--     if value then
+--         set_status(string.format("通道0: %.2f", value))
+--     end
+-- end)
+
+
+-- 脚本面板示例：PID参数调节和GPIO控制(取消注释即可使用)
+-- ui_slider("Kp", 0, 10, function(v) send(string.format("KP=%.3f\r\n", v)) end, 1.0)
+-- ui_slider("PWM", 0, 100, function(v) send({0x55, 0x10, math.floor(v)}) end)
+-- ui_checkbox("GPIO1", function(on) send({0x55, 0x20, on and 1 or 0}) end)
+-- ui_button("复位", function() send_hex("55 FF") end)
+-- ui_separator()
+-- ui_label("通道0", nil)
+-- set_timer(200, function() ui_set("通道0", plot_value(0)) end)
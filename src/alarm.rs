@@ -0,0 +1,255 @@
+use crate::sample_buffer::Sample;
+use chrono::{DateTime, Local};
+use std::collections::{BTreeMap, VecDeque};
+use std::io::Write;
+
+// 报警设置的保存位置
+pub const ALARM_SETTINGS_PATH: &str = "config/alarms.cfg";
+
+// 事件列表最多保留的条数
+const MAX_EVENTS: usize = 10_000;
+
+// 越限后执行的动作
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AlarmAction {
+    None,
+    StopCapture,
+    SendCommand,
+}
+
+impl AlarmAction {
+    pub const ALL: [AlarmAction; 3] = [AlarmAction::None, AlarmAction::StopCapture, AlarmAction::SendCommand];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AlarmAction::None => "无",
+            AlarmAction::StopCapture => "停止采集",
+            AlarmAction::SendCommand => "发送命令",
+        }
+    }
+
+    fn key(&self) -> &'static str {
+        match self {
+            AlarmAction::None => "none",
+            AlarmAction::StopCapture => "stop",
+            AlarmAction::SendCommand => "send",
+        }
+    }
+
+    fn from_key(key: &str) -> Self {
+        match key {
+            "stop" => AlarmAction::StopCapture,
+            "send" => AlarmAction::SendCommand,
+            _ => AlarmAction::None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AlarmLevel {
+    Normal,
+    High,
+    Low,
+}
+
+impl AlarmLevel {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AlarmLevel::Normal => "恢复正常",
+            AlarmLevel::High => "超过上限",
+            AlarmLevel::Low => "低于下限",
+        }
+    }
+}
+
+// 一个通道的报警设置
+#[derive(Clone, PartialEq, Debug)]
+pub struct ChannelAlarm {
+    pub enabled: bool,
+    pub high_enabled: bool,
+    pub high: f64,
+    pub low_enabled: bool,
+    pub low: f64,
+    pub action: AlarmAction,
+    pub command: String,
+    pub command_hex: bool,
+    pub beep: bool,  // 播放系统提示音
+    pub flash: bool, // 闪烁任务栏提醒
+    level: AlarmLevel,
+}
+
+impl Default for ChannelAlarm {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            high_enabled: true,
+            high: 1.0,
+            low_enabled: false,
+            low: 0.0,
+            action: AlarmAction::None,
+            command: String::new(),
+            command_hex: false,
+            beep: false,
+            flash: true,
+            level: AlarmLevel::Normal,
+        }
+    }
+}
+
+impl ChannelAlarm {
+    fn level_of(&self, value: f64) -> AlarmLevel {
+        if self.high_enabled && value > self.high {
+            AlarmLevel::High
+        } else if self.low_enabled && value < self.low {
+            AlarmLevel::Low
+        } else {
+            AlarmLevel::Normal
+        }
+    }
+}
+
+// 越限或恢复的事件
+pub struct AlarmEvent {
+    pub time: DateTime<Local>,
+    pub channel: usize,
+    pub level: AlarmLevel,
+    pub sample: Sample,
+}
+
+// 越限时需要由界面执行的动作
+pub struct AlarmTrigger {
+    pub channel: usize,
+    pub level: AlarmLevel,
+    pub value: f64,
+    pub alarm: ChannelAlarm,
+}
+
+#[derive(Default)]
+pub struct Alarms {
+    pub limits: BTreeMap<usize, ChannelAlarm>,
+    pub events: VecDeque<AlarmEvent>,
+    pub show: bool,
+    pub show_markers: bool,
+    pub editing_channel: usize,
+    pending: Vec<AlarmTrigger>,
+}
+
+impl Alarms {
+    pub fn new(limits: BTreeMap<usize, ChannelAlarm>) -> Self {
+        Self {
+            limits,
+            show_markers: true,
+            ..Default::default()
+        }
+    }
+
+    // 检查新数据点，只在状态改变时记录事件，越限时安排执行动作
+    pub fn check(&mut self, channel: usize, sample: Sample, time: DateTime<Local>) {
+        let Some(alarm) = self.limits.get_mut(&channel).filter(|a| a.enabled) else {
+            return;
+        };
+        let level = alarm.level_of(sample.y);
+        if level == alarm.level {
+            return;
+        }
+        alarm.level = level;
+
+        self.events.push_back(AlarmEvent { time, channel, level, sample });
+        while self.events.len() > MAX_EVENTS {
+            self.events.pop_front();
+        }
+        if level != AlarmLevel::Normal {
+            self.pending.push(AlarmTrigger {
+                channel,
+                level,
+                value: sample.y,
+                alarm: alarm.clone(),
+            });
+        }
+    }
+
+    pub fn take_pending(&mut self) -> Vec<AlarmTrigger> {
+        std::mem::take(&mut self.pending)
+    }
+
+    // 修改设置后重新判断状态，下一个数据点如果仍然越限会再次报警
+    pub fn reset_state(&mut self, channel: usize) {
+        if let Some(alarm) = self.limits.get_mut(&channel) {
+            alarm.level = AlarmLevel::Normal;
+        }
+    }
+}
+
+// 从配置文件恢复报警设置，每行格式:
+// 通道号<TAB>启用<TAB>上限启用<TAB>上限<TAB>下限启用<TAB>下限<TAB>动作<TAB>HEX<TAB>响铃<TAB>闪烁<TAB>命令
+pub fn load_alarm_settings(path: &str) -> BTreeMap<usize, ChannelAlarm> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return BTreeMap::new();
+    };
+
+    content
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.splitn(11, '\t').collect();
+            if parts.len() < 11 {
+                return None;
+            }
+            let flag = |i: usize| parts[i] != "0";
+            let alarm = ChannelAlarm {
+                enabled: flag(1),
+                high_enabled: flag(2),
+                high: parts[3].parse().ok()?,
+                low_enabled: flag(4),
+                low: parts[5].parse().ok()?,
+                action: AlarmAction::from_key(parts[6]),
+                command_hex: flag(7),
+                beep: flag(8),
+                flash: flag(9),
+                command: parts[10].to_string(),
+                level: AlarmLevel::Normal,
+            };
+            Some((parts[0].parse().ok()?, alarm))
+        })
+        .collect()
+}
+
+pub fn save_alarm_settings(path: &str, limits: &BTreeMap<usize, ChannelAlarm>) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    let flag = |value: bool| if value { 1 } else { 0 };
+    for (channel, alarm) in limits {
+        writeln!(
+            file,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            channel,
+            flag(alarm.enabled),
+            flag(alarm.high_enabled),
+            alarm.high,
+            flag(alarm.low_enabled),
+            alarm.low,
+            alarm.action.key(),
+            flag(alarm.command_hex),
+            flag(alarm.beep),
+            flag(alarm.flash),
+            alarm.command.replace(['\t', '\n'], " ")
+        )?;
+    }
+    Ok(())
+}
+
+// 把事件列表导出为 CSV
+pub fn export_events(path: &std::path::Path, events: &VecDeque<AlarmEvent>, name_of: impl Fn(usize) -> String) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    writeln!(file, "time,channel,name,event,value")?;
+    for event in events {
+        writeln!(
+            file,
+            "{},{},{},{},{}",
+            event.time.format("%Y-%m-%d %H:%M:%S%.3f"),
+            event.channel,
+            name_of(event.channel).replace(',', " "),
+            event.level.label(),
+            event.sample.y
+        )?;
+    }
+    Ok(())
+}
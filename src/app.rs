@@ -8,9 +8,24 @@ use serialport::{DataBits};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use std::io::{Read, Write};  
-use mlua::Lua;
 use sysinfo::System;
-use crate::serial::SerialPortHandle;
+use crate::serial::{RxChunk, SerialPortHandle};
+use crate::script::{LuaScript, ParsedFrame};
+use crate::channel::{self, PlotChannel};
+use crate::sample_buffer::{HistoryLimit, Sample, XAxisMode};
+use crate::trigger::Trigger;
+use crate::measure::{Cursors, Histogram, Statistics};
+use crate::fft::Spectrum;
+use crate::derived::{self, DerivedChannels, DerivedEditor};
+use crate::xy::{PlotMode, XySettings};
+use crate::alarm::{self, AlarmAction, Alarms};
+use crate::dashboard::{self, Dashboard};
+use crate::import;
+use crate::reference::References;
+use crate::receive_buffer::{ReceiveBuffer, RECEIVE_LIMITS};
+use crate::receive_view::{HexSelection, ReceiveDisplay};
+use chrono::{DateTime, Local};
+use std::collections::{BTreeMap, BTreeSet};
 // 在 SerialAssistant 结构体中添加新字段
 pub struct SerialAssistant {
     pub ports: Vec<serialport::SerialPortInfo>,
@@ -18,9 +33,10 @@ pub struct SerialAssistant {
     pub baud_rates: Vec<u32>,
     pub selected_baud: u32,
     pub port_handle: Option<SerialPortHandle>,  // 修改类型
-    pub received_data: String,
+    pub received: ReceiveBuffer,  // 接收区域显示的原始数据，超过上限时丢弃最早的数据
+    pub hex_selection: HexSelection,  // HEX 视图中选中的字节
     pub send_data: String,
-    pub is_hex_display: bool,
+    pub receive_display: ReceiveDisplay,  // 接收区域按文本、HEX 或混合方式显示
     pub is_hex_send: bool,
     pub data_bits: DataBits,
     pub stop_bits: serialport::StopBits,
@@ -35,8 +51,7 @@ pub struct SerialAssistant {
     pub bytes_sent_last: usize,
     pub log_enabled: bool,
     pub log_file: Option<String>,
-    pub received_data_shared: Arc<Mutex<String>>,
-    pub received_buffer: Vec<u8>,
+    pub received_data_shared: Arc<Mutex<Vec<RxChunk>>>,
     pub auto_scroll: bool,
     pub status_message: String,
     pub last_stats_update: Instant,
@@ -45,11 +60,40 @@ pub struct SerialAssistant {
     pub packet_buffer: Vec<u8>,
     pub plot_data: Vec<(f64, f64)>,
     pub plot_visible: bool,
-    pub received_bytes: Vec<u8>,
-    pub lua_script_path: String,  // 添加一个字段来存储Lua文件的路径
-    pub lua_state: Option<Lua>,
-    pub plot_data_per_channel: Vec<Vec<(f64, f64)>>,  // 添加一个字段来存储每个通道的绘图数据
-    pub plot_data_per_channel_x: Vec<usize>,  
+    pub script: LuaScript,  // Lua脚本及其错误、控制台输出
+    pub show_script: bool,
+    pub script_status: String,  // 脚本通过 set_status 设置的状态栏信息
+    pub alarm_status: String,  // 最近一次报警，显示在状态栏
+    pub send_error: String,  // 发送区内容格式错误，显示在状态栏，下次发送成功时清除
+    pub plot_data_per_channel: BTreeMap<usize, PlotChannel>,  // 按通道号存储的绘图数据，收到数据时按需创建
+    pub show_channel_panel: bool,
+    pub history_limit: HistoryLimit,  // 每个通道保留的历史长度
+    pub capture_epoch: DateTime<Local>,  // 数据点时间戳的起点
+    pub x_axis_mode: XAxisMode,           // 横轴: 采样序号、相对时间或绝对时间
+    pub trigger: Trigger,                 // 示波器式触发设置和冻结的捕获波形
+    pub wave_paused: bool,                // 暂停时波形视图不再跟随新数据，后台继续采集
+    pub wave_scroll: Option<f64>,         // 时间轴拖动到的视图起点，下一帧应用
+    pub wave_go_live: bool,               // 回到实时显示，下一帧恢复自动缩放
+    pub wave_visible_x: Option<[f64; 2]>, // 上一帧波形可见的横轴范围(原始横坐标)
+    pub plot_hover: Option<[f64; 2]>,     // 鼠标在波形中的坐标
+    pub cursors: Cursors,                 // 测量光标
+    pub statistics: Statistics,           // 每通道统计表
+    pub spectrum: Spectrum,               // 频谱面板
+    pub histogram: Histogram,             // 数值分布直方图
+    pub derived: DerivedChannels,         // 由表达式或滤波器计算的派生通道
+    pub derived_editor: DerivedEditor,
+    pub show_derived: bool,
+    pub plot_mode: PlotMode,              // 时域波形或 XY 图
+    pub xy: XySettings,
+    pub stacked_lanes: bool,              // 每个通道单独一道，各自自动缩放
+    pub alarms: Alarms,                   // 通道上下限报警和事件记录
+    pub dashboard: Dashboard,             // 仪表盘窗口的控件
+    pub references: References,           // 叠加显示的参考波形
+    pub export_visible_only: bool,        // 导出 CSV 时只导出当前视图范围
+    pub wave_export_png: Option<std::path::PathBuf>, // 等待截图保存的 PNG 路径
+    pub wave_plot_rect: Option<egui::Rect>,          // 波形区域，截图时按它裁剪
+    pub file_message: Option<String>,                // 最近一次导入、导出或保存文件的结果，显示在波形工具栏
+    pub frame_fields: Vec<(String, String)>,  // 最近一帧解析结果中的命名字段
     pub tcp_enabled: bool,
     pub tcp_address: String,
     pub tcp_port: String,
@@ -65,15 +109,16 @@ pub struct SerialAssistant {
 
 impl Default for SerialAssistant {
     fn default() -> Self {
-        Self {
+        let mut app = Self {
             ports: serialport::available_ports().unwrap_or_default(),
             selected_port: String::new(),
             baud_rates: vec![9600, 19200, 38400, 57600, 115200],
             selected_baud: 115200,
             port_handle: None,
-            received_data: String::new(),
+            received: ReceiveBuffer::new(RECEIVE_LIMITS[2]),
+            hex_selection: HexSelection::default(),
             send_data: String::new(),
-            is_hex_display: false,
+            receive_display: ReceiveDisplay::Text,
             is_hex_send: false,
             data_bits: DataBits::Eight,
             stop_bits: serialport::StopBits::One,
@@ -88,8 +133,7 @@ impl Default for SerialAssistant {
             bytes_sent_last: 0,
             log_enabled: false,
             log_file: None,
-            received_data_shared: Arc::new(Mutex::new(String::new())),
-            received_buffer: Vec::new(),
+            received_data_shared: Arc::new(Mutex::new(Vec::new())),
             auto_scroll: true,
             status_message: String::new(),
             last_stats_update: Instant::now(),
@@ -98,11 +142,40 @@ impl Default for SerialAssistant {
             packet_buffer: Vec::new(),
             plot_data: Vec::with_capacity(1000),
             plot_visible: false,
-            received_bytes: Vec::new(),
-            plot_data_per_channel: vec![Vec::with_capacity(1000); 10],
-            plot_data_per_channel_x: vec![0; 10],
-            lua_script_path: String::from("config/waveform.lua"),
-            lua_state: None,
+            plot_data_per_channel: channel::load_channel_settings(channel::CHANNEL_SETTINGS_PATH),
+            show_channel_panel: true,
+            history_limit: HistoryLimit::Points(10_000),
+            capture_epoch: Local::now(),
+            x_axis_mode: XAxisMode::Index,
+            trigger: Trigger::default(),
+            wave_paused: false,
+            wave_scroll: None,
+            wave_go_live: false,
+            wave_visible_x: None,
+            plot_hover: None,
+            cursors: Cursors::default(),
+            statistics: Statistics::default(),
+            spectrum: Spectrum::default(),
+            histogram: Histogram::default(),
+            derived: DerivedChannels::new(derived::load_derived_settings(derived::DERIVED_SETTINGS_PATH)),
+            derived_editor: DerivedEditor::default(),
+            show_derived: false,
+            plot_mode: PlotMode::TimeSeries,
+            xy: XySettings::default(),
+            stacked_lanes: false,
+            alarms: Alarms::new(alarm::load_alarm_settings(alarm::ALARM_SETTINGS_PATH)),
+            dashboard: dashboard::load_dashboard_settings(dashboard::DASHBOARD_SETTINGS_PATH),
+            references: References::default(),
+            export_visible_only: false,
+            wave_export_png: None,
+            wave_plot_rect: None,
+            file_message: None,
+            frame_fields: Vec::new(),
+            script: LuaScript::new("config/waveform.lua"),
+            show_script: false,
+            script_status: String::new(),
+            alarm_status: String::new(),
+            send_error: String::new(),
             tcp_enabled: false,
             tcp_address: String::from("127.0.0.1"),
             tcp_port: String::from("8080"),
@@ -114,7 +187,9 @@ impl Default for SerialAssistant {
             pointer_pos: Pos2::new(0.0, 0.0),
             sys: System::new_all(),
             last_cpu_usage: 0.0,
-        }
+        };
+        app.apply_derived_names();
+        app
     }
 }
 
@@ -159,6 +234,11 @@ impl SerialAssistant {
 
     // 记录数据
     pub fn log_data_with_lock(&self, data: &[u8], is_received: bool) {
+        self.log_data_at(data, is_received, Local::now());
+    }
+
+    // 使用指定的时间戳记录数据，接收数据使用读取线程记录的时间，便于与波形时间轴对应
+    pub fn log_data_at(&self, data: &[u8], is_received: bool, time: DateTime<Local>) {
         if self.log_enabled {
             if let Some(ref path) = self.log_file {
                 if let Ok(mut file) = std::fs::OpenOptions::new()
@@ -166,7 +246,7 @@ impl SerialAssistant {
                     .append(true)
                     .open(path) 
                 {
-                    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f");
+                    let timestamp = time.format("%Y-%m-%d %H:%M:%S%.3f");
                     let direction = if is_received { "RX" } else { "TX" };
                     let hex_data = utils::bytes_to_hex(data);
                     
@@ -180,20 +260,242 @@ impl SerialAssistant {
         }
     }
 
-     // 初始化Lua环境
-    pub fn init_lua(&mut self) {         
-        if self.lua_state.is_none() {
-            let lua = Lua::new();
+     // 初始化Lua环境，脚本文件修改后自动重新加载
+    pub fn init_lua(&mut self) {
+        self.script.ensure_loaded();
+        self.script.poll_reload();
+    }
+
+    // 通过当前连接(TCP或串口)发送数据，返回实际发送的字节数
+    pub fn send_bytes(&mut self, data: &[u8]) -> usize {
+        let written = if self.tcp_enabled {
+            self.tcp_stream.as_ref().and_then(|tcp| tcp.lock().ok()?.write(data).ok())
+        } else {
+            self.port_handle.as_ref().and_then(|port| port.lock().ok()?.write(data).ok())
+        }
+        .unwrap_or(0);
+
+        if written > 0 {
+            self.bytes_sent += written;
+            self.log_data_with_lock(&data[..written], false);
+        }
+        written
+    }
 
-            let script_content = std::fs::read_to_string(&self.lua_script_path)
-                .expect("无法读取Lua脚本文件");
-            // 尝试加载外部Lua脚本文件
-            if let Err(e) = lua.load(&script_content).exec() {
-                println!("Lua脚本加载失败: {}", e);
+    // 驱动脚本：执行定时器并处理脚本请求的操作
+    pub fn run_script(&mut self) {
+        let connected = self.port_handle.is_some() || self.tcp_connected;
+        if self.plot_visible || self.dashboard.visible || self.show_script || connected {
+            self.init_lua();
+        }
+        if self.script.lua.is_none() {
+            return;
+        }
+
+        self.script.run_timers();
+        self.apply_script_actions();
+    }
+
+    // 执行脚本通过API请求的发送、状态栏和波形操作
+    fn apply_script_actions(&mut self) {
+        let actions = self.script.take_actions();
+
+        for data in &actions.outgoing {
+            if self.send_bytes(data) == 0 {
+                self.script.log("发送失败: 未连接");
             }
-            
-            self.lua_state = Some(lua);
-            println!("Lua环境初始化成功");
+        }
+        if let Some(status) = actions.status {
+            self.script_status = status;
+        }
+        for channel in actions.plot_clears {
+            if let Some(channel) = self.plot_data_per_channel.get_mut(&channel) {
+                channel.clear();
+            }
+        }
+        let now = self.capture_time(Local::now());
+        for (channel, value) in actions.plot_points {
+            self.push_plot_sample(channel, None, value, now);
+        }
+        self.update_derived_channels();
+    }
+
+    // 相对于采集开始的秒数
+    pub fn capture_time(&self, time: DateTime<Local>) -> f64 {
+        (time - self.capture_epoch).num_microseconds().unwrap_or_default() as f64 / 1e6
+    }
+
+    // 向通道添加一个接收到的数据点，没有指定 X 时使用该通道的采样序号，t 为数据到达时间
+    fn push_plot_sample(&mut self, channel: usize, x: Option<f64>, y_value: f64, t: f64) {
+        self.derived.mark_raw(channel);
+        self.push_channel_sample(channel, x, y_value, t);
+    }
+
+    // 接收数据和派生通道的输出共用的添加数据点逻辑
+    fn push_channel_sample(&mut self, channel: usize, x: Option<f64>, y_value: f64, t: f64) {
+        // 以这个通道为输入的滤波通道同时计算，派生通道也可以作为其他派生通道的输入
+        // 添加派生通道时已经排除了循环引用，队列一定会结束
+        let mut queue = vec![(channel, x, y_value)];
+        while let Some((channel, x, y_value)) = queue.pop() {
+            let derived = &self.derived;
+            let plot_channel = self.plot_data_per_channel
+                .entry(channel)
+                .or_insert_with(|| {
+                    let mut plot_channel = PlotChannel::new(channel);
+                    if let Some(definition) = derived.channels.iter().find(|c| c.index == channel) {
+                        plot_channel.name = definition.name.clone();
+                    }
+                    plot_channel
+                });
+
+            let sample = Sample {
+                x: plot_channel.next_sample_x(x),
+                y: y_value,
+                t,
+            };
+            plot_channel.data.push(sample, self.history_limit);
+            self.script.set_channel_value(channel, y_value);
+            let time = self.capture_epoch + chrono::Duration::microseconds((t * 1e6) as i64);
+            self.alarms.check(channel, sample, time);
+
+            for (index, value) in self.derived.on_sample(channel, sample) {
+                queue.push((index, Some(sample.x), value));
+            }
+        }
+    }
+
+    // 一帧数据处理完后计算引用了新数据的表达式通道
+    // 表达式的输入也可以是其他表达式，按依赖顺序反复计算，直到没有需要更新的表达式
+    fn update_derived_channels(&mut self) {
+        let mut evaluated = BTreeSet::new();
+        for _ in 0..=self.derived.channels.len() {
+            let pending = self.derived.pending_expressions(&evaluated);
+            if pending.is_empty() {
+                break;
+            }
+            for index in pending {
+                evaluated.insert(index);
+                for (driver, value) in self.derived.evaluate(index, &self.plot_data_per_channel) {
+                    self.push_channel_sample(index, Some(driver.x), value, driver.t);
+                }
+            }
+        }
+        self.derived.finish_round();
+    }
+
+    // 派生通道的名称作为通道名称显示
+    pub fn apply_derived_names(&mut self) {
+        for definition in &self.derived.channels {
+            if let Some(channel) = self.plot_data_per_channel.get_mut(&definition.index) {
+                channel.name = definition.name.clone();
+            }
+        }
+    }
+
+    pub fn save_derived_settings(&mut self) {
+        if let Err(e) = derived::save_derived_settings(derived::DERIVED_SETTINGS_PATH, &self.derived.channels) {
+            println!("保存派生通道失败: {}", e);
+        }
+    }
+
+    pub fn save_alarm_settings(&mut self) {
+        if let Err(e) = alarm::save_alarm_settings(alarm::ALARM_SETTINGS_PATH, &self.alarms.limits) {
+            println!("保存报警设置失败: {}", e);
+        }
+    }
+
+    pub fn save_dashboard_settings(&mut self) {
+        if let Err(e) = dashboard::save_dashboard_settings(dashboard::DASHBOARD_SETTINGS_PATH, &self.dashboard) {
+            println!("保存仪表盘失败: {}", e);
+        }
+    }
+
+    // 执行越限报警配置的提醒和动作
+    fn run_alarm_actions(&mut self, ctx: &egui::Context) {
+        for trigger in self.alarms.take_pending() {
+            let name = self.plot_data_per_channel
+                .get(&trigger.channel)
+                .map(|c| c.display_name().to_string())
+                .unwrap_or_else(|| format!("通道{}", trigger.channel));
+            self.alarm_status = format!(
+                "{} {} {} ({:.4})",
+                Local::now().format("%H:%M:%S"),
+                name,
+                trigger.level.label(),
+                trigger.value
+            );
+
+            let alarm = trigger.alarm;
+            if alarm.beep {
+                utils::system_beep();
+            }
+            if alarm.flash {
+                ctx.send_viewport_cmd(egui::ViewportCommand::RequestUserAttention(egui::UserAttentionType::Critical));
+            }
+            match alarm.action {
+                AlarmAction::None => {}
+                AlarmAction::StopCapture => {
+                    if self.tcp_connected {
+                        self.disconnect_tcp();
+                    } else {
+                        self.close_port();
+                    }
+                    self.auto_send_active = false;
+                }
+                AlarmAction::SendCommand => {
+                    let data = if alarm.command_hex {
+                        utils::hex_to_bytes(&alarm.command).unwrap_or_else(|e| {
+                            self.alarm_status.push_str(&format!(", 命令格式错误: {}", e));
+                            Vec::new()
+                        })
+                    } else {
+                        alarm.command.as_bytes().to_vec()
+                    };
+                    if !data.is_empty() && self.send_bytes(&data) == 0 {
+                        self.alarm_status.push_str(", 命令发送失败: 未连接");
+                    }
+                }
+            }
+        }
+    }
+
+    // 发送区域的内容转换为要发送的字节，HEX 格式错误时不发送，错误显示在状态栏
+    pub fn send_payload(&mut self) -> Vec<u8> {
+        if !self.is_hex_send {
+            self.send_error.clear();
+            return self.send_data.as_bytes().to_vec();
+        }
+        match utils::hex_to_bytes(&self.send_data) {
+            Ok(data) => {
+                self.send_error.clear();
+                data
+            }
+            Err(e) => {
+                self.send_error = format!("HEX发送格式错误: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    // 修改历史长度后立即裁剪已有数据
+    pub fn set_history_limit(&mut self, limit: HistoryLimit) {
+        self.history_limit = limit;
+        for channel in self.plot_data_per_channel.values_mut() {
+            channel.data.trim(limit);
+        }
+    }
+
+    // 退出暂停，波形重新跟随最新数据
+    pub fn resume_live(&mut self) {
+        self.wave_paused = false;
+        self.wave_scroll = None;
+        self.wave_go_live = true;
+    }
+
+    // 保存通道的名称、颜色和显示设置
+    pub fn save_channel_settings(&mut self) {
+        if let Err(e) = channel::save_channel_settings(channel::CHANNEL_SETTINGS_PATH, &self.plot_data_per_channel) {
+            println!("保存通道设置失败: {}", e);
         }
     }
 
@@ -268,7 +570,6 @@ impl SerialAssistant {
             self.bytes_send_per_second = 0.0;
             self.bytes_received_per_second = 0.0;
             self.packet_buffer.clear();
-            self.received_buffer.clear();
             
             println!("串口已关闭");
             true
@@ -297,32 +598,14 @@ impl SerialAssistant {
         println!("TCP断开连接");
     }
 
-    pub fn process_received_data(&mut self, data: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn process_received_data(&mut self, data: &[u8], time: DateTime<Local>) -> Result<(), Box<dyn std::error::Error>> {
         // 将数据添加到缓冲区
         self.packet_buffer.extend_from_slice(data);
-        self.bytes_received += data.len();        
-       
-        println!("接收完成，处理数据: {:02X?}", self.packet_buffer);
-        
+        self.bytes_received += data.len();
+
         // TCP模式和串口模式都可以使用波形显示功能
-        if self.plot_visible {
-            if let Some(lua) = &self.lua_state {
-                let mut frames_to_process = Vec::new();
-            
-                if let Ok(frame_length) = lua.globals().get::<usize>("FRAME_LENGTH") {
-                    println!("用户填写的帧长度: {}", frame_length);
-            
-                    // 检查是否有完整的数据帧
-                    while self.packet_buffer.len() >= frame_length {
-                        let frame = self.packet_buffer.drain(0..frame_length).collect::<Vec<u8>>();
-                        frames_to_process.push(frame);
-                    }
-                    
-                    for frame in frames_to_process {
-                        self.process_frame(&frame)?;
-                    }
-                }
-            }
+        if self.needs_frames() {
+            self.parse_frames(time)?;
         }
         
         // 缓冲区超过最大长度时清空（防止内存溢出）
@@ -334,53 +617,152 @@ impl SerialAssistant {
         Ok(())
     }
 
-    fn process_frame(&mut self, frame: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
-        if let Some(lua) = &self.lua_state {
-            let parse_fn = lua.globals().get::<mlua::Function>("parse_waveform")?;
-            let lua_data = lua.create_table()?;
-       
-            for (i, &byte) in frame.iter().enumerate() {
-                lua_data.set(i + 1, byte)?;
-            }
-            
-            let result: mlua::Result<Option<mlua::Table>> = parse_fn.call(lua_data);
-            
-            if let Ok(Some(result_table)) = result {
-                if let Ok(channel) = result_table.get::<Option<u8>>("channel") {
-                    if let Some(channel) = channel {
-                        let channel: usize = channel as usize;
-                        
-                        if channel <= 9 {
-                            let points: mlua::Table = result_table.get("points")?;
-                            
-                            if channel < self.plot_data_per_channel.len() {
-                                for y in points.sequence_values::<f64>() {
-                                    if let Ok(y_value) = y {
-                                        // let x_value = self.plot_data_per_channel[channel].len() as f64;
-                                        self.plot_data_per_channel_x[channel] += 1;
-                                        let x_value = self.plot_data_per_channel_x[channel] as f64;
-                                        println!("通道 {}: x={}, y={}", channel, x_value, y_value);
-                                        self.plot_data_per_channel[channel].push((x_value, y_value as f64));
-                                    }
-                                }
-                                
-                                // 保持数据点数量限制
-                                while self.plot_data_per_channel[channel].len() > 950 {
-                                    // self.plot_data_per_channel[channel].remove(0);
-                                    let len = self.plot_data_per_channel[channel].len();
-                                    let end = len - 950;
-                                    self.plot_data_per_channel[channel].drain(0..end);
-                                }
-                            }
-                        }
-                    }
-                } else {
-                    println!("通道号解析失败: 未返回有效的通道号");
+    // 波形和仪表盘使用解析出的通道数据；报警、派生通道和脚本回调在窗口关闭时也要继续工作
+    fn needs_frames(&self) -> bool {
+        self.plot_visible
+            || self.dashboard.visible
+            || self.alarms.limits.values().any(|alarm| alarm.enabled)
+            || !self.derived.channels.is_empty()
+            || self.script.host.borrow().has_callbacks()
+    }
+
+    // 从缓冲区中取出完整的帧交给解析脚本
+    fn parse_frames(&mut self, time: DateTime<Local>) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(lua) = &self.script.lua {
+            let mut frames_to_process = Vec::new();
+        
+            if let Ok(frame_length) = lua.globals().get::<usize>("FRAME_LENGTH") {
+                // 检查是否有完整的数据帧
+                while self.packet_buffer.len() >= frame_length {
+                    let frame = self.packet_buffer.drain(0..frame_length).collect::<Vec<u8>>();
+                    frames_to_process.push(frame);
+                }
+                
+                // 帧的时间戳取收到帧最后一个字节的时间
+                let t = self.capture_time(time);
+                for frame in frames_to_process {
+                    self.process_frame(&frame, t)?;
                 }
             }
         }
         Ok(())
     }
+
+    // 清空所有通道的数据，保留通道设置
+    pub fn clear_plot_data(&mut self) {
+        for channel in self.plot_data_per_channel.values_mut() {
+            channel.clear();
+        }
+        // 清空数据后派生通道的滤波器从头计算
+        self.derived.reset();
+        self.trigger.rearm();
+    }
+
+    // 把 CSV 或接收日志导入波形，替换现有数据，返回导入结果说明
+    pub fn import_file(&mut self, path: &std::path::Path) -> Result<String, String> {
+        let is_csv = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        let result = if is_csv { self.import_csv(path) } else { self.import_capture_log(path) };
+        // 历史数据触发的报警只记录事件，不执行发送命令等动作
+        self.alarms.take_pending();
+        self.statistics.invalidate();
+        self.spectrum.invalidate();
+        self.resume_live();
+        result
+    }
+
+    fn import_csv(&mut self, path: &std::path::Path) -> Result<String, String> {
+        let csv = import::read_csv(path)?;
+        self.clear_plot_data();
+
+        for (index, name) in &csv.channels {
+            self.plot_data_per_channel
+                .entry(*index)
+                .or_insert_with(|| PlotChannel::new(*index))
+                .name = name.clone();
+        }
+
+        // 没有时间列时按行号作为秒数
+        let first_time = csv.rows.iter().find_map(|row| Some((row.time?, row.t)));
+        self.capture_epoch = match first_time {
+            Some((time, t)) => time - chrono::Duration::microseconds((t.unwrap_or_default() * 1e6) as i64),
+            None => Local::now(),
+        };
+        let epoch = self.capture_epoch;
+        for (row_index, row) in csv.rows.iter().enumerate() {
+            let t = row
+                .t
+                .or_else(|| row.time.map(|time| (time - epoch).num_microseconds().unwrap_or_default() as f64 / 1e6))
+                .unwrap_or(row_index as f64);
+            for value in &row.values {
+                self.push_plot_sample(value.channel, value.x, value.y, t);
+            }
+            self.update_derived_channels();
+        }
+        Ok(format!("已导入 {} 行, {} 个通道", csv.rows.len(), csv.channels.len()))
+    }
+
+    // 接收日志中的数据按原始时间重新经过解析脚本，与实时接收的处理相同
+    fn import_capture_log(&mut self, path: &std::path::Path) -> Result<String, String> {
+        let chunks = import::read_capture_log(path)?;
+        self.init_lua();
+        if self.script.lua.is_none() {
+            return Err("解析脚本未加载".to_string());
+        }
+        self.clear_plot_data();
+        self.packet_buffer.clear();
+        self.capture_epoch = chunks[0].time;
+
+        let bytes: usize = chunks.iter().map(|chunk| chunk.data.len()).sum();
+        for chunk in &chunks {
+            self.packet_buffer.extend_from_slice(&chunk.data);
+            self.parse_frames(chunk.time).map_err(|e| e.to_string())?;
+        }
+        self.packet_buffer.clear();
+        Ok(format!("已导入 {} 条接收记录, 共 {} 字节", chunks.len(), bytes))
+    }
+
+    fn process_frame(&mut self, frame: &[u8], t: f64) -> Result<(), Box<dyn std::error::Error>> {
+        // 解析函数出错或超出限制时错误显示在脚本窗口中，不影响数据接收
+        let parsed = match self.script.call(|lua| call_parse_waveform(lua, frame)) {
+            Some(Some(parsed)) => parsed,
+            _ => return Ok(()),
+        };
+
+        for sample in parsed.samples {
+            self.push_plot_sample(sample.channel, sample.x, sample.y, t);
+        }
+        self.update_derived_channels();
+        for info in parsed.channel_info {
+            let plot_channel = self.plot_data_per_channel
+                .entry(info.channel)
+                .or_insert_with(|| PlotChannel::new(info.channel));
+            if let Some(name) = info.name {
+                plot_channel.name = name;
+            }
+            if let Some(unit) = info.unit {
+                plot_channel.unit = unit;
+            }
+        }
+        if !parsed.fields.is_empty() {
+            self.frame_fields = parsed.fields;
+        }
+        Ok(())
+    }
+}
+
+// 调用脚本中的 parse_waveform 函数解析一帧数据
+fn call_parse_waveform(lua: &mlua::Lua, frame: &[u8]) -> mlua::Result<Option<ParsedFrame>> {
+    let parse_fn = lua.globals().get::<mlua::Function>("parse_waveform")?;
+    let lua_data = lua.create_table()?;
+
+    for (i, &byte) in frame.iter().enumerate() {
+        lua_data.set(i + 1, byte)?;
+    }
+
+    match parse_fn.call::<Option<mlua::Table>>(lua_data)? {
+        Some(result) => ParsedFrame::from_table(&result).map(Some),
+        None => Ok(None),
+    }
 }
 
 impl eframe::App for SerialAssistant {
@@ -389,7 +771,7 @@ impl eframe::App for SerialAssistant {
         self.frame_history
             .on_new_frame(ctx.input(|i| i.time), frame.info().cpu_usage);
 
-        let mut data_to_process = Vec::new();
+        let mut chunks = Vec::new();
         
         // 处理TCP数据接收
         if self.tcp_connected {
@@ -400,7 +782,10 @@ impl eframe::App for SerialAssistant {
                 if let Ok(mut stream) = tcp.try_lock() {
                     match stream.read(&mut buffer) {
                         Ok(n) if n > 0 => {
-                            data_to_process.extend_from_slice(&buffer[..n]);
+                            chunks.push(RxChunk {
+                                time: Local::now(),
+                                data: buffer[..n].to_vec(),
+                            });
                         },
                         Ok(_) => {
                             should_disconnect = true;
@@ -418,51 +803,25 @@ impl eframe::App for SerialAssistant {
             if should_disconnect {
                 self.disconnect_tcp();
             }
-            
-            if !data_to_process.is_empty() {
-                let _  = self.process_received_data(&data_to_process);
-            }
         }
         
         // 处理串口数据接收
         if !self.tcp_enabled {
             if let Ok(mut received) = self.received_data_shared.try_lock() {
-                if !received.is_empty() {
-                    data_to_process = received.chars()
-                        .filter_map(|c| {
-                            if c as u32 >= 0xFF00 {
-                                Some((c as u32 - 0xFF00) as u8)
-                            } else {
-                                None
-                            }
-                        })
-                        .collect();
-                    received.clear();
-                }
+                chunks.append(&mut received);
             }            
         }
         
-        // 然后处理数据
+        // 然后处理数据，每段数据使用各自的接收时间
+        for chunk in &chunks {
+            let _ = self.process_received_data(&chunk.data, chunk.time);
+            self.script.on_receive(&chunk.data);
+        }
+
+        let data_to_process: Vec<u8> = chunks.iter().flat_map(|c| c.data.iter().copied()).collect();
         if !data_to_process.is_empty() {
-            let _ = self.process_received_data(&data_to_process);
-            
-            // 更新缓冲区
-            self.received_buffer.extend(&data_to_process);
-            
             // 更新显示区域
-            if self.is_hex_display {
-                let mut hex_string = String::new();
-                for &byte in data_to_process.iter() {
-                    hex_string.push_str(&format!("{:02X} ", byte));
-                }
-                self.received_data.push_str(&hex_string);
-                self.received_data.push('\n'); 
-            } else {
-                // 文本显示模式，支持汉字等 UTF-8 字符
-                let text = String::from_utf8_lossy(&data_to_process);
-                self.received_data.push_str(&text);
-                self.received_data.push('\n');                
-            }
+            self.received.push(&data_to_process);
             
             // 记录日志
             if self.log_enabled {
@@ -476,7 +835,9 @@ impl eframe::App for SerialAssistant {
                         println!("日志文件保存位置: {}", self.log_file.as_ref().unwrap());
                     }
                 }
-                self.log_data_with_lock(&data_to_process, true);
+                for chunk in &chunks {
+                    self.log_data_at(&chunk.data, true, chunk.time);
+                }
             } else {
                 // 取消勾选时关闭日志文件
                 self.log_file = None;
@@ -488,17 +849,12 @@ impl eframe::App for SerialAssistant {
         // 自动发送逻辑
         if self.auto_send && self.auto_send_active && 
            self.last_send_time.elapsed().as_millis() as u64 >= self.auto_send_interval {
-            
+            let data = self.send_payload();
+
             // TCP 模式的自动发送
             if self.tcp_enabled && self.tcp_connected {
                 if let Some(tcp) = &self.tcp_stream {
                     if let Ok(mut stream) = tcp.lock() {
-                        let data = if self.is_hex_send {
-                            utils::hex_to_bytes(&self.send_data)
-                        } else {
-                            self.send_data.as_bytes().to_vec()
-                        };
-                        
                         println!("TCP发送数据: {:?}", data);
                         if let Ok(written) = stream.write(&data) {
                             self.bytes_sent += written;
@@ -512,13 +868,6 @@ impl eframe::App for SerialAssistant {
             else if let Some(port) = &self.port_handle {
                 if let Ok(mut port) = port.lock() {
                     println!("原始输入: {}", self.send_data);
-                    
-                    let data = if self.is_hex_send {
-                        utils::hex_to_bytes(&self.send_data)
-                    } else {
-                        self.send_data.as_bytes().to_vec()
-                    };
-                    
                     println!("发送数据: {:?}", data);
                     println!("发送数据(HEX): {}", data.iter()
                         .map(|b| format!("{:02X}", b))
@@ -537,6 +886,10 @@ impl eframe::App for SerialAssistant {
             ctx.request_repaint_after(Duration::from_millis(self.auto_send_interval));
         }
 
+        // 执行脚本定时器和脚本请求的操作
+        self.run_script();
+        self.run_alarm_actions(ctx);
+
         // 渲染UI
         ui::render_ui(self, ctx);
         ctx.request_repaint();
@@ -0,0 +1,235 @@
+use crate::sample_buffer::SampleBuffer;
+use egui::Color32;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+// 通道设置(名称、颜色、是否显示)的保存位置
+pub const CHANNEL_SETTINGS_PATH: &str = "config/channels.cfg";
+
+// 数字通道最多显示的位数
+pub const MAX_BIT_COUNT: usize = 32;
+
+// 默认调色板，通道号超过调色板长度时循环使用
+const PALETTE: [Color32; 10] = [
+    Color32::from_rgb(255, 0, 0),     // 红色
+    Color32::from_rgb(0, 255, 0),     // 绿色
+    Color32::from_rgb(0, 0, 255),     // 蓝色
+    Color32::from_rgb(255, 255, 0),   // 黄色
+    Color32::from_rgb(255, 0, 255),   // 紫色
+    Color32::from_rgb(0, 255, 255),   // 青色
+    Color32::from_rgb(128, 0, 128),   // 深紫色
+    Color32::from_rgb(128, 128, 0),   // 橄榄色
+    Color32::from_rgb(0, 128, 128),   // 深青色
+    Color32::from_rgb(128, 128, 128), // 灰色
+];
+
+pub fn default_color(index: usize) -> Color32 {
+    PALETTE[index % PALETTE.len()]
+}
+
+// 一个波形通道，按需创建
+pub struct PlotChannel {
+    pub name: String,              // 解析脚本提供的名称
+    pub unit: String,              // 解析脚本提供的单位
+    pub user_name: Option<String>, // 用户重命名后的名称，优先于脚本提供的名称
+    pub color: Color32,
+    pub visible: bool,
+    pub gain: f64,   // 显示时的增益，不影响存储的数据
+    pub offset: f64, // 显示时的偏移
+    pub bit_count: usize,       // 大于 0 时按位显示为逻辑分析仪式的数字通道
+    pub bit_names: Vec<String>, // 每一位的名称，为空时显示位号
+    pub data: SampleBuffer,
+    pub next_x: usize, // 没有指定 X 时使用的采样序号
+    x_shift: f64,      // 脚本提供的 X 回绕或复位后累计的平移量
+}
+
+impl PlotChannel {
+    pub fn new(index: usize) -> Self {
+        Self {
+            name: format!("通道 {}", index),
+            unit: String::new(),
+            user_name: None,
+            color: default_color(index),
+            visible: true,
+            gain: 1.0,
+            offset: 0.0,
+            bit_count: 0,
+            bit_names: Vec::new(),
+            data: SampleBuffer::default(),
+            next_x: 0,
+            x_shift: 0.0,
+        }
+    }
+
+    pub fn display_name(&self) -> &str {
+        self.user_name.as_deref().unwrap_or(&self.name)
+    }
+
+    // 图例中显示的名称，带单位
+    pub fn label(&self) -> String {
+        if self.unit.is_empty() {
+            self.display_name().to_string()
+        } else {
+            format!("{} ({})", self.display_name(), self.unit)
+        }
+    }
+
+    pub fn is_logic(&self) -> bool {
+        self.bit_count > 0
+    }
+
+    pub fn bit_name(&self, bit: usize) -> String {
+        match self.bit_names.get(bit).filter(|name| !name.is_empty()) {
+            Some(name) => name.clone(),
+            None => format!("{}.{}", self.display_name(), bit),
+        }
+    }
+
+    pub fn is_scaled(&self) -> bool {
+        self.gain != 1.0 || self.offset != 0.0
+    }
+
+    // 应用增益和偏移后的显示值
+    pub fn scaled(&self, y: f64) -> f64 {
+        y * self.gain + self.offset
+    }
+
+    // 波形图例中的名称，设置了增益或偏移时附带说明，避免误读数值
+    pub fn plot_label(&self) -> String {
+        if self.is_scaled() {
+            format!("{} ×{} {:+}", self.label(), self.gain, self.offset)
+        } else {
+            self.label()
+        }
+    }
+
+    // 新数据点的横坐标，没有指定 X 时使用采样序号
+    // 查找可见范围要求横坐标单调递增，脚本提供的 X 变小时(计数器回绕、时间戳复位)
+    // 把之后的数据平移到上一个点之后，间隔取上一次的步长
+    pub fn next_sample_x(&mut self, x: Option<f64>) -> f64 {
+        self.next_x += 1;
+        let x = x.filter(|x| x.is_finite()).unwrap_or(self.next_x as f64) + self.x_shift;
+        let Some(last) = self.data.last().map(|s| s.x) else {
+            return x;
+        };
+        if x >= last {
+            return x;
+        }
+        let step = self.data.len().checked_sub(2).and_then(|i| self.data.get(i)).map_or(0.0, |previous| (last - previous.x).max(0.0));
+        self.x_shift += last + step - x;
+        last + step
+    }
+
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.next_x = 0;
+        self.x_shift = 0.0;
+    }
+}
+
+// 从配置文件恢复通道设置，每行格式:
+// 通道号<TAB>名称<TAB>R,G,B<TAB>是否显示<TAB>增益<TAB>偏移<TAB>位数<TAB>位名称(逗号分隔)
+// 名称为空表示使用脚本提供的名称，旧版本的文件没有后面几列
+pub fn load_channel_settings(path: &str) -> BTreeMap<usize, PlotChannel> {
+    let mut channels = BTreeMap::new();
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return channels;
+    };
+
+    for line in content.lines() {
+        let parts: Vec<&str> = line.split('\t').collect();
+        if parts.len() < 4 {
+            continue;
+        }
+        let Ok(index) = parts[0].parse::<usize>() else {
+            continue;
+        };
+
+        let mut channel = PlotChannel::new(index);
+        if !parts[1].is_empty() {
+            channel.user_name = Some(parts[1].to_string());
+        }
+        let rgb: Vec<u8> = parts[2].split(',').filter_map(|v| v.trim().parse().ok()).collect();
+        if let [r, g, b] = rgb[..] {
+            channel.color = Color32::from_rgb(r, g, b);
+        }
+        channel.visible = parts[3] != "0";
+        if let Some(gain) = parts.get(4).and_then(|v| v.parse().ok()) {
+            channel.gain = gain;
+        }
+        if let Some(offset) = parts.get(5).and_then(|v| v.parse().ok()) {
+            channel.offset = offset;
+        }
+        if let Some(bit_count) = parts.get(6).and_then(|v| v.parse::<usize>().ok()) {
+            channel.bit_count = bit_count.min(MAX_BIT_COUNT);
+        }
+        if let Some(names) = parts.get(7).filter(|v| !v.is_empty()) {
+            channel.bit_names = names.split(',').map(str::to_string).collect();
+        }
+        channels.insert(index, channel);
+    }
+    channels
+}
+
+pub fn save_channel_settings(path: &str, channels: &BTreeMap<usize, PlotChannel>) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    for (index, channel) in channels {
+        writeln!(
+            file,
+            "{}\t{}\t{},{},{}\t{}\t{}\t{}\t{}\t{}",
+            index,
+            channel.user_name.as_deref().unwrap_or_default().replace('\t', " "),
+            channel.color.r(),
+            channel.color.g(),
+            channel.color.b(),
+            if channel.visible { 1 } else { 0 },
+            channel.gain,
+            channel.offset,
+            channel.bit_count,
+            channel.bit_names.iter().map(|name| name.replace([',', '\t'], " ")).collect::<Vec<_>>().join(",")
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample_buffer::{HistoryLimit, Sample};
+
+    fn push_all(channel: &mut PlotChannel, xs: &[Option<f64>]) -> Vec<f64> {
+        xs.iter()
+            .map(|&x| {
+                let x = channel.next_sample_x(x);
+                channel.data.push(Sample { x, y: 0.0, t: 0.0 }, HistoryLimit::Points(1000));
+                x
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sample_index_without_x() {
+        let mut channel = PlotChannel::new(0);
+        assert_eq!(push_all(&mut channel, &[None, None, None]), vec![1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn rebases_x_when_it_wraps() {
+        let mut channel = PlotChannel::new(0);
+        let xs = [0.0, 1.0, 2.0, 0.0, 1.0, 0.0, 10.0, 10.0].map(Some);
+        // 每次回绕后接在上一个点之后，间隔取上一次的步长，之后的点保持相同的平移
+        assert_eq!(push_all(&mut channel, &xs), vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 15.0, 15.0]);
+
+        channel.clear();
+        assert_eq!(push_all(&mut channel, &[Some(7.0), Some(3.0)]), vec![7.0, 7.0]);
+    }
+
+    #[test]
+    fn mixes_script_x_and_sample_index() {
+        let mut channel = PlotChannel::new(0);
+        let xs = [Some(100.0), Some(101.5), None, Some(f64::NAN), Some(200.0), Some(201.0), Some(0.5)];
+        // 没有 X 或 X 无效时的采样序号小于脚本提供的 X，同样接在上一个点之后，之后的 X 都加上同样的平移
+        assert_eq!(push_all(&mut channel, &xs), vec![100.0, 101.5, 103.0, 104.0, 300.0, 301.0, 302.0]);
+        assert!(channel.data.iter().map(|s| s.x).collect::<Vec<_>>().windows(2).all(|w| w[0] <= w[1]));
+    }
+}
@@ -0,0 +1,226 @@
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+// 仪表盘布局的保存位置
+pub const DASHBOARD_SETTINGS_PATH: &str = "config/dashboard.cfg";
+// 网格的最大列数
+pub const MAX_COLUMNS: usize = 8;
+// 编辑时最后一次修改后多久保存，避免每次输入或拖动都写文件
+const SAVE_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WidgetKind {
+    Value,     // 大号数值
+    Gauge,     // 指针表盘
+    Bar,       // 柱状条
+    Led,       // 指示灯
+    Sparkline, // 迷你曲线
+}
+
+impl WidgetKind {
+    pub const ALL: [WidgetKind; 5] = [WidgetKind::Value, WidgetKind::Gauge, WidgetKind::Bar, WidgetKind::Led, WidgetKind::Sparkline];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WidgetKind::Value => "数值",
+            WidgetKind::Gauge => "表盘",
+            WidgetKind::Bar => "柱状条",
+            WidgetKind::Led => "指示灯",
+            WidgetKind::Sparkline => "迷你曲线",
+        }
+    }
+
+    fn key(&self) -> &'static str {
+        match self {
+            WidgetKind::Value => "value",
+            WidgetKind::Gauge => "gauge",
+            WidgetKind::Bar => "bar",
+            WidgetKind::Led => "led",
+            WidgetKind::Sparkline => "sparkline",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.key() == key)
+    }
+}
+
+// 绑定到一个通道的仪表盘控件，数值已应用通道的增益和偏移
+#[derive(Clone, PartialEq, Debug)]
+pub struct DashboardWidget {
+    pub kind: WidgetKind,
+    pub channel: usize,
+    pub title: String, // 为空时显示通道名称
+    pub min: f64,      // 表盘和柱状条的量程
+    pub max: f64,
+    pub threshold: f64, // 指示灯在数值大于等于阈值时点亮
+    pub decimals: usize,
+    pub points: usize, // 迷你曲线显示的点数
+}
+
+impl DashboardWidget {
+    pub fn new(kind: WidgetKind, channel: usize) -> Self {
+        Self {
+            kind,
+            channel,
+            title: String::new(),
+            min: 0.0,
+            max: 100.0,
+            threshold: 0.5,
+            decimals: 2,
+            points: 200,
+        }
+    }
+
+    // 数值在量程中的比例，限制在 0..1
+    pub fn fraction(&self, value: f64) -> f32 {
+        if self.max > self.min {
+            ((value - self.min) / (self.max - self.min)).clamp(0.0, 1.0) as f32
+        } else {
+            0.0
+        }
+    }
+}
+
+pub struct Dashboard {
+    pub visible: bool,
+    pub editing: bool,
+    pub columns: usize,
+    pub widgets: Vec<DashboardWidget>,
+    changed_at: Option<Instant>, // 最后一次未保存的修改
+}
+
+impl Dashboard {
+    pub fn new(columns: usize, widgets: Vec<DashboardWidget>) -> Self {
+        Self {
+            visible: false,
+            editing: widgets.is_empty(),
+            columns: columns.clamp(1, MAX_COLUMNS),
+            widgets,
+            changed_at: None,
+        }
+    }
+
+    pub fn mark_changed(&mut self) {
+        self.changed_at = Some(Instant::now());
+    }
+
+    // 有未保存的修改，并且已退出编辑、关闭了窗口或一段时间没有新的修改时返回 true，之后视为已保存
+    pub fn take_pending_save(&mut self) -> bool {
+        let due = self
+            .changed_at
+            .is_some_and(|changed| !self.editing || !self.visible || changed.elapsed() >= SAVE_DELAY);
+        if due {
+            self.changed_at = None;
+        }
+        due
+    }
+}
+
+impl Default for Dashboard {
+    fn default() -> Self {
+        Self::new(3, Vec::new())
+    }
+}
+
+// 从配置文件恢复布局，第一行为 columns<TAB>列数(旧版本的文件没有)，之后每行一个控件:
+// 类型<TAB>通道号<TAB>最小值<TAB>最大值<TAB>阈值<TAB>小数位<TAB>点数<TAB>标题
+pub fn load_dashboard_settings(path: &str) -> Dashboard {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return Dashboard::default();
+    };
+
+    let columns = content
+        .lines()
+        .find_map(|line| line.strip_prefix("columns\t")?.trim().parse().ok())
+        .unwrap_or(Dashboard::default().columns);
+    let widgets = content
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.splitn(8, '\t').collect();
+            if parts.len() < 8 {
+                return None;
+            }
+            Some(DashboardWidget {
+                kind: WidgetKind::from_key(parts[0])?,
+                channel: parts[1].parse().ok()?,
+                min: parts[2].parse().ok()?,
+                max: parts[3].parse().ok()?,
+                threshold: parts[4].parse().ok()?,
+                decimals: parts[5].parse().ok()?,
+                points: parts[6].parse().ok()?,
+                title: parts[7].to_string(),
+            })
+        })
+        .collect();
+    Dashboard::new(columns, widgets)
+}
+
+pub fn save_dashboard_settings(path: &str, dashboard: &Dashboard) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    writeln!(file, "columns\t{}", dashboard.columns)?;
+    for widget in &dashboard.widgets {
+        writeln!(
+            file,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            widget.kind.key(),
+            widget.channel,
+            widget.min,
+            widget.max,
+            widget.threshold,
+            widget.decimals,
+            widget.points,
+            widget.title.replace(['\t', '\n'], " ")
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_round_trip_keeps_columns() {
+        let path = std::env::temp_dir().join(format!("dashboard_{}.cfg", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut widget = DashboardWidget::new(WidgetKind::Gauge, 4);
+        widget.title = "压力".to_string();
+        save_dashboard_settings(path, &Dashboard::new(5, vec![widget.clone()])).unwrap();
+        let loaded = load_dashboard_settings(path);
+        assert_eq!((loaded.columns, loaded.widgets), (5, vec![widget.clone()]));
+
+        // 旧版本的文件没有列数
+        std::fs::write(path, "led\t1\t0\t1\t0.5\t0\t200\t\n").unwrap();
+        let loaded = load_dashboard_settings(path);
+        let _ = std::fs::remove_file(path);
+        assert_eq!(loaded.columns, 3);
+        assert_eq!(loaded.widgets.len(), 1);
+        assert!(!loaded.editing);
+    }
+
+    #[test]
+    fn saves_after_editing_ends() {
+        let mut dashboard = Dashboard::new(3, Vec::new());
+        dashboard.visible = true;
+        assert!(dashboard.editing);
+        assert!(!dashboard.take_pending_save());
+
+        // 编辑中刚修改过不保存，退出编辑后保存一次
+        dashboard.mark_changed();
+        assert!(!dashboard.take_pending_save());
+        dashboard.editing = false;
+        assert!(dashboard.take_pending_save());
+        assert!(!dashboard.take_pending_save());
+
+        // 编辑中一段时间没有新的修改时保存
+        dashboard.editing = true;
+        dashboard.changed_at = Some(Instant::now() - SAVE_DELAY);
+        assert!(dashboard.take_pending_save());
+
+        // 关闭窗口时保存
+        dashboard.mark_changed();
+        dashboard.visible = false;
+        assert!(dashboard.take_pending_save());
+    }
+}
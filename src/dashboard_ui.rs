@@ -0,0 +1,286 @@
+use crate::app::SerialAssistant;
+use crate::channel::PlotChannel;
+use crate::dashboard::{DashboardWidget, WidgetKind, MAX_COLUMNS};
+use eframe::egui;
+use egui::{Color32, Pos2, Rect, Stroke, Vec2};
+use std::f32::consts::PI;
+
+const CELL_HEIGHT: f32 = 150.0;
+const LED_ON: Color32 = Color32::from_rgb(0, 220, 0);
+
+enum WidgetAction {
+    MoveLeft(usize),
+    MoveRight(usize),
+    Remove(usize),
+}
+
+// 仪表盘窗口：按网格排列绑定到通道的数值、表盘、柱状条、指示灯和迷你曲线
+pub fn render_dashboard_viewport(app: &mut SerialAssistant, ctx: &egui::Context) {
+    let before = (app.dashboard.columns, app.dashboard.widgets.clone());
+
+    egui::TopBottomPanel::top("dashboard_toolbar").show(ctx, |ui| {
+        ui.horizontal_wrapped(|ui| {
+            ui.toggle_value(&mut app.dashboard.editing, "编辑");
+            ui.label("列数:");
+            ui.add(egui::DragValue::new(&mut app.dashboard.columns).range(1..=MAX_COLUMNS));
+            if app.dashboard.editing && ui.button("添加控件").clicked() {
+                let channel = app.plot_data_per_channel.keys().next().copied().unwrap_or(0);
+                app.dashboard.widgets.push(DashboardWidget::new(WidgetKind::Value, channel));
+            }
+        });
+    });
+
+    egui::CentralPanel::default().show(ctx, |ui| {
+        if app.dashboard.widgets.is_empty() {
+            ui.label("暂无控件, 点击编辑后添加控件并选择通道");
+            return;
+        }
+
+        let columns = app.dashboard.columns.max(1);
+        let spacing = ui.spacing().item_spacing.x;
+        let cell_width = ((ui.available_width() - spacing * (columns as f32 - 1.0)) / columns as f32 - 12.0).max(80.0);
+        let editing = app.dashboard.editing;
+        let mut action = None;
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (row, widgets) in app.dashboard.widgets.chunks_mut(columns).enumerate() {
+                ui.horizontal_top(|ui| {
+                    for (column, widget) in widgets.iter_mut().enumerate() {
+                        let index = row * columns + column;
+                        let channel = app.plot_data_per_channel.get(&widget.channel);
+                        egui::Frame::group(ui.style()).show(ui, |ui| {
+                            ui.set_width(cell_width);
+                            ui.vertical(|ui| {
+                                render_widget(ui, widget, channel, cell_width);
+                                if editing {
+                                    ui.separator();
+                                    action = widget_editor(ui, widget, index).or(action.take());
+                                }
+                            });
+                        });
+                    }
+                });
+            }
+        });
+
+        let widgets = &mut app.dashboard.widgets;
+        match action {
+            Some(WidgetAction::MoveLeft(index)) if index > 0 => widgets.swap(index, index - 1),
+            Some(WidgetAction::MoveRight(index)) if index + 1 < widgets.len() => widgets.swap(index, index + 1),
+            Some(WidgetAction::Remove(index)) => {
+                widgets.remove(index);
+            }
+            _ => {}
+        }
+    });
+
+    if (app.dashboard.columns, &app.dashboard.widgets) != (before.0, &before.1) {
+        app.dashboard.mark_changed();
+    }
+
+    if ctx.input(|i| i.viewport().close_requested()) {
+        app.dashboard.visible = false;
+    }
+    if app.dashboard.take_pending_save() {
+        app.save_dashboard_settings();
+    }
+}
+
+fn render_widget(ui: &mut egui::Ui, widget: &DashboardWidget, channel: Option<&PlotChannel>, width: f32) {
+    let title = match (widget.title.is_empty(), channel) {
+        (false, _) => widget.title.clone(),
+        (true, Some(channel)) => channel.display_name().to_string(),
+        (true, None) => format!("通道 {}", widget.channel),
+    };
+    let unit = channel.map_or("", |c| c.unit.as_str());
+    let color = channel.map_or(Color32::GRAY, |c| c.color);
+    let value = channel.and_then(|c| Some(c.scaled(c.data.last()?.y)));
+    let text = value.map_or("--".to_string(), |v| format!("{:.*} {}", widget.decimals, v, unit));
+
+    ui.vertical_centered(|ui| {
+        ui.strong(title);
+    });
+    let size = Vec2::new(width, CELL_HEIGHT - 24.0);
+
+    match widget.kind {
+        WidgetKind::Value => {
+            ui.allocate_ui(size, |ui| {
+                ui.centered_and_justified(|ui| {
+                    ui.label(egui::RichText::new(text).monospace().size(36.0).color(color));
+                });
+            });
+        }
+        WidgetKind::Gauge => {
+            let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+            draw_gauge(ui, rect, widget, value, color, &text);
+        }
+        WidgetKind::Bar => {
+            let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+            draw_bar(ui, rect, widget, value, color, &text);
+        }
+        WidgetKind::Led => {
+            let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+            let on = value.is_some_and(|v| v >= widget.threshold);
+            let painter = ui.painter();
+            let radius = (rect.height() * 0.3).min(30.0);
+            let center = rect.center() - Vec2::new(0.0, 10.0);
+            painter.circle(center, radius, if on { LED_ON } else { Color32::DARK_GRAY }, Stroke::new(2.0, Color32::GRAY));
+            painter.text(
+                Pos2::new(rect.center().x, rect.bottom() - 4.0),
+                egui::Align2::CENTER_BOTTOM,
+                text,
+                egui::FontId::monospace(14.0),
+                ui.visuals().text_color(),
+            );
+        }
+        WidgetKind::Sparkline => {
+            let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+            draw_sparkline(ui, rect, widget, channel, color, &text);
+        }
+    }
+}
+
+// 270° 的表盘，从左下角经顶部到右下角
+fn draw_gauge(ui: &egui::Ui, rect: Rect, widget: &DashboardWidget, value: Option<f64>, color: Color32, text: &str) {
+    let painter = ui.painter();
+    let radius = (rect.width().min(rect.height() * 1.3) * 0.42).max(10.0);
+    let center = Pos2::new(rect.center().x, rect.top() + radius + 8.0);
+    let (start, sweep) = (0.75 * PI, 1.5 * PI);
+    let arc = |from: f32, to: f32| -> Vec<Pos2> {
+        let steps = 48;
+        (0..=steps)
+            .map(|i| {
+                let angle = start + sweep * (from + (to - from) * i as f32 / steps as f32);
+                center + radius * Vec2::angled(angle)
+            })
+            .collect()
+    };
+
+    painter.add(egui::Shape::line(arc(0.0, 1.0), Stroke::new(8.0, ui.visuals().faint_bg_color)));
+    if let Some(value) = value {
+        let fraction = widget.fraction(value);
+        painter.add(egui::Shape::line(arc(0.0, fraction), Stroke::new(8.0, color)));
+        let needle = center + (radius - 12.0) * Vec2::angled(start + sweep * fraction);
+        painter.line_segment([center, needle], Stroke::new(2.0, ui.visuals().strong_text_color()));
+    }
+    painter.circle_filled(center, 4.0, ui.visuals().strong_text_color());
+
+    let small = egui::FontId::proportional(11.0);
+    let weak = ui.visuals().weak_text_color();
+    painter.text(center + radius * Vec2::angled(start) + Vec2::new(0.0, 6.0), egui::Align2::CENTER_TOP, format!("{}", widget.min), small.clone(), weak);
+    painter.text(center + radius * Vec2::angled(start + sweep) + Vec2::new(0.0, 6.0), egui::Align2::CENTER_TOP, format!("{}", widget.max), small, weak);
+    painter.text(
+        Pos2::new(center.x, rect.bottom() - 4.0),
+        egui::Align2::CENTER_BOTTOM,
+        text,
+        egui::FontId::monospace(16.0),
+        ui.visuals().text_color(),
+    );
+}
+
+fn draw_bar(ui: &egui::Ui, rect: Rect, widget: &DashboardWidget, value: Option<f64>, color: Color32, text: &str) {
+    let painter = ui.painter();
+    let bar = Rect::from_min_size(Pos2::new(rect.left() + 8.0, rect.center().y - 14.0), Vec2::new(rect.width() - 16.0, 28.0));
+    painter.rect_filled(bar, 4.0, ui.visuals().faint_bg_color);
+    if let Some(value) = value {
+        let mut filled = bar;
+        filled.set_width(bar.width() * widget.fraction(value));
+        painter.rect_filled(filled, 4.0, color);
+    }
+
+    let small = egui::FontId::proportional(11.0);
+    let weak = ui.visuals().weak_text_color();
+    painter.text(bar.left_bottom() + Vec2::new(0.0, 4.0), egui::Align2::LEFT_TOP, format!("{}", widget.min), small.clone(), weak);
+    painter.text(bar.right_bottom() + Vec2::new(0.0, 4.0), egui::Align2::RIGHT_TOP, format!("{}", widget.max), small, weak);
+    painter.text(
+        Pos2::new(rect.center().x, bar.top() - 6.0),
+        egui::Align2::CENTER_BOTTOM,
+        text,
+        egui::FontId::monospace(16.0),
+        ui.visuals().text_color(),
+    );
+}
+
+// 迷你曲线按最近几个点的范围自动缩放
+fn draw_sparkline(ui: &egui::Ui, rect: Rect, widget: &DashboardWidget, channel: Option<&PlotChannel>, color: Color32, text: &str) {
+    let painter = ui.painter();
+    let area = Rect::from_min_max(rect.min + Vec2::new(4.0, 4.0), rect.max - Vec2::new(4.0, 24.0));
+    painter.rect_stroke(area, 2.0, Stroke::new(1.0, ui.visuals().faint_bg_color), egui::StrokeKind::Inside);
+
+    if let Some(channel) = channel {
+        let len = channel.data.len();
+        let start = len.saturating_sub(widget.points.max(2));
+        let values: Vec<f64> = channel.data.range(start..len).map(|s| channel.scaled(s.y)).collect();
+        let (min, max) = values.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| (min.min(*v), max.max(*v)));
+        if values.len() > 1 {
+            let span = if max > min { max - min } else { 1.0 };
+            let points: Vec<Pos2> = values
+                .iter()
+                .enumerate()
+                .map(|(i, v)| {
+                    let x = area.left() + area.width() * i as f32 / (values.len() - 1) as f32;
+                    let y = area.bottom() - area.height() * ((v - min) / span) as f32;
+                    Pos2::new(x, y)
+                })
+                .collect();
+            painter.add(egui::Shape::line(points, Stroke::new(1.5, color)));
+        }
+    }
+    painter.text(
+        Pos2::new(rect.center().x, rect.bottom() - 2.0),
+        egui::Align2::CENTER_BOTTOM,
+        text,
+        egui::FontId::monospace(14.0),
+        ui.visuals().text_color(),
+    );
+}
+
+fn widget_editor(ui: &mut egui::Ui, widget: &mut DashboardWidget, index: usize) -> Option<WidgetAction> {
+    let mut action = None;
+    ui.horizontal_wrapped(|ui| {
+        egui::ComboBox::from_id_salt(("dashboard_widget_kind", index))
+            .selected_text(widget.kind.label())
+            .show_ui(ui, |ui| {
+                for kind in WidgetKind::ALL {
+                    ui.selectable_value(&mut widget.kind, kind, kind.label());
+                }
+            });
+        ui.label("通道:");
+        ui.add(egui::DragValue::new(&mut widget.channel));
+    });
+    ui.horizontal_wrapped(|ui| {
+        ui.label("标题:");
+        ui.add(egui::TextEdit::singleline(&mut widget.title).hint_text("通道名称").desired_width(100.0));
+    });
+    ui.horizontal_wrapped(|ui| match widget.kind {
+        WidgetKind::Gauge | WidgetKind::Bar => {
+            ui.label("量程:");
+            ui.add(egui::DragValue::new(&mut widget.min).speed(0.1));
+            ui.label("~");
+            ui.add(egui::DragValue::new(&mut widget.max).speed(0.1));
+        }
+        WidgetKind::Led => {
+            ui.label("点亮阈值:");
+            ui.add(egui::DragValue::new(&mut widget.threshold).speed(0.01));
+        }
+        WidgetKind::Sparkline => {
+            ui.label("点数:");
+            ui.add(egui::DragValue::new(&mut widget.points).range(2..=10_000));
+        }
+        WidgetKind::Value => {}
+    });
+    ui.horizontal_wrapped(|ui| {
+        ui.label("小数位:");
+        ui.add(egui::DragValue::new(&mut widget.decimals).range(0..=9));
+        if ui.small_button("◀").clicked() {
+            action = Some(WidgetAction::MoveLeft(index));
+        }
+        if ui.small_button("▶").clicked() {
+            action = Some(WidgetAction::MoveRight(index));
+        }
+        if ui.small_button("删除").clicked() {
+            action = Some(WidgetAction::Remove(index));
+        }
+    });
+    action
+}
@@ -0,0 +1,590 @@
+use crate::channel::PlotChannel;
+use crate::sample_buffer::Sample;
+use mlua::{Function, HookTriggers, Lua, LuaOptions, StdLib, VmState};
+use std::cell::Cell;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::io::Write;
+use std::rc::Rc;
+
+// 派生通道定义的保存位置
+pub const DERIVED_SETTINGS_PATH: &str = "config/derived.cfg";
+
+// 表达式每次求值最多执行的指令数(以 1000 条为单位)，防止死循环卡住界面
+const EXPRESSION_INSTRUCTION_LIMIT: u32 = 100;
+// 表达式环境可使用的最大内存
+const EXPRESSION_MEMORY_LIMIT: usize = 8 * 1024 * 1024;
+
+// 派生通道的计算方式
+#[derive(Clone, PartialEq, Debug)]
+pub enum DerivedKind {
+    Expression(String),                             // 表达式，如 ch0 - ch1、sqrt(ch2^2 + ch3^2)
+    MovingAverage { source: usize, window: usize }, // 最近 window 个点的平均值
+    LowPass { source: usize, alpha: f64 },          // 一阶低通: y += alpha * (x - y)
+    Derivative { source: usize },                   // 对横坐标求导
+    Integral { source: usize },                     // 对横坐标积分(梯形法)
+}
+
+impl DerivedKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DerivedKind::Expression(_) => "表达式",
+            DerivedKind::MovingAverage { .. } => "滑动平均",
+            DerivedKind::LowPass { .. } => "低通滤波",
+            DerivedKind::Derivative { .. } => "微分",
+            DerivedKind::Integral { .. } => "积分",
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            DerivedKind::Expression(expression) => expression.clone(),
+            DerivedKind::MovingAverage { source, window } => format!("avg(ch{}, {})", source, window),
+            DerivedKind::LowPass { source, alpha } => format!("lowpass(ch{}, {})", source, alpha),
+            DerivedKind::Derivative { source } => format!("d(ch{})/dx", source),
+            DerivedKind::Integral { source } => format!("∫ch{} dx", source),
+        }
+    }
+
+    // 滤波类通道的输入通道
+    fn source(&self) -> Option<usize> {
+        match self {
+            DerivedKind::Expression(_) => None,
+            DerivedKind::MovingAverage { source, .. }
+            | DerivedKind::LowPass { source, .. }
+            | DerivedKind::Derivative { source }
+            | DerivedKind::Integral { source } => Some(*source),
+        }
+    }
+}
+
+// 滤波器的运行状态
+#[derive(Default)]
+struct FilterState {
+    window: VecDeque<f64>,
+    sum: f64,
+    last: Option<Sample>,
+    value: f64,
+}
+
+pub struct DerivedChannel {
+    pub index: usize, // 输出通道号
+    pub name: String,
+    pub kind: DerivedKind,
+    pub error: Option<String>,
+    inputs: Vec<usize>, // 表达式引用的通道
+    function: Option<Function>,
+    state: FilterState,
+}
+
+impl DerivedChannel {
+    pub fn new(index: usize, name: String, kind: DerivedKind) -> Self {
+        let inputs = match &kind {
+            DerivedKind::Expression(expression) => expression_inputs(expression),
+            _ => Vec::new(),
+        };
+        Self {
+            index,
+            name,
+            kind,
+            error: None,
+            inputs,
+            function: None,
+            state: FilterState::default(),
+        }
+    }
+
+    // 计算这个通道要用到的通道
+    fn input_channels(&self) -> Vec<usize> {
+        match self.kind.source() {
+            Some(source) => vec![source],
+            None => self.inputs.clone(),
+        }
+    }
+
+    // 滤波类通道处理输入通道的一个新数据点，返回输出值
+    fn filter(&mut self, sample: Sample) -> Option<f64> {
+        let state = &mut self.state;
+        let output = match self.kind {
+            DerivedKind::Expression(_) => return None,
+            DerivedKind::MovingAverage { window, .. } => {
+                state.window.push_back(sample.y);
+                state.sum += sample.y;
+                while state.window.len() > window.max(1) {
+                    state.sum -= state.window.pop_front().unwrap_or_default();
+                }
+                Some(state.sum / state.window.len() as f64)
+            }
+            DerivedKind::LowPass { alpha, .. } => {
+                state.value = match state.last {
+                    Some(_) => state.value + alpha.clamp(0.0, 1.0) * (sample.y - state.value),
+                    None => sample.y,
+                };
+                Some(state.value)
+            }
+            DerivedKind::Derivative { .. } => state
+                .last
+                .filter(|last| sample.x != last.x)
+                .map(|last| (sample.y - last.y) / (sample.x - last.x)),
+            DerivedKind::Integral { .. } => {
+                if let Some(last) = state.last {
+                    state.value += (sample.y + last.y) * 0.5 * (sample.x - last.x);
+                }
+                Some(state.value)
+            }
+        };
+        state.last = Some(sample);
+        output
+    }
+}
+
+// 表达式中引用的通道号，即 ch 后面跟数字的标识符
+fn expression_inputs(expression: &str) -> Vec<usize> {
+    let mut inputs = BTreeSet::new();
+    let bytes = expression.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let is_word_start = i == 0 || !(bytes[i - 1].is_ascii_alphanumeric() || bytes[i - 1] == b'_');
+        if is_word_start && bytes[i..].starts_with(b"ch") {
+            let digits = bytes[i + 2..].iter().take_while(|b| b.is_ascii_digit()).count();
+            let end = i + 2 + digits;
+            let is_word_end = end == bytes.len() || !(bytes[end].is_ascii_alphanumeric() || bytes[end] == b'_');
+            if digits > 0 && is_word_end {
+                if let Ok(index) = expression[i + 2..end].parse() {
+                    inputs.insert(index);
+                }
+                i = end;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    inputs.into_iter().collect()
+}
+
+// 派生通道编辑窗口中正在填写的定义
+pub struct DerivedEditor {
+    pub index: usize,
+    pub name: String,
+    pub kind: DerivedKind,
+    pub error: Option<String>,
+}
+
+impl Default for DerivedEditor {
+    fn default() -> Self {
+        Self {
+            index: 100,
+            name: String::new(),
+            kind: DerivedKind::Expression("ch0 - ch1".to_string()),
+            error: None,
+        }
+    }
+}
+
+// 所有派生通道，表达式在独立的 Lua 环境中求值，只开放数学函数
+pub struct DerivedChannels {
+    pub channels: Vec<DerivedChannel>,
+    pub error: Option<String>, // 表达式环境创建失败或配置中的派生通道无法加载
+    lua: Option<Lua>,
+    instructions: Rc<Cell<u32>>,
+    updated: BTreeMap<usize, Vec<Sample>>, // 本轮各通道的新数据点
+    raw: BTreeSet<usize>,     // 收到过接收数据(解析脚本、plot 或导入)的通道
+}
+
+impl DerivedChannels {
+    pub fn new(channels: Vec<DerivedChannel>) -> Self {
+        let instructions = Rc::new(Cell::new(0));
+        let (lua, error) = match create_expression_lua(Rc::clone(&instructions)) {
+            Ok(lua) => (Some(lua), None),
+            Err(e) => (None, Some(format!("创建表达式环境失败: {}", e))),
+        };
+        let mut derived = Self {
+            channels: Vec::new(),
+            error,
+            lua,
+            instructions,
+            updated: BTreeMap::new(),
+            raw: BTreeSet::new(),
+        };
+        for channel in channels {
+            let index = channel.index;
+            if let Err(e) = derived.add(channel) {
+                derived.error = Some(format!("派生通道 {} 未加载: {}", index, e));
+            }
+        }
+        derived
+    }
+
+    pub fn is_derived(&self, index: usize) -> bool {
+        self.channels.iter().any(|c| c.index == index)
+    }
+
+    // 添加或替换同一输出通道的派生通道，表达式编译失败时返回错误
+    pub fn add(&mut self, mut channel: DerivedChannel) -> Result<(), String> {
+        if self.raw.contains(&channel.index) {
+            return Err(format!("通道 {} 已有接收数据，请选择其他输出通道", channel.index));
+        }
+        if self.forms_cycle(&channel) {
+            return Err("输入通道不能直接或经过其他派生通道引用输出通道本身".to_string());
+        }
+        if let DerivedKind::Expression(_) = channel.kind {
+            let lua = self.lua.as_ref().ok_or("表达式环境不可用")?;
+            compile(lua, &mut channel);
+        }
+        if let Some(error) = channel.error {
+            return Err(error);
+        }
+        match self.channels.iter_mut().find(|c| c.index == channel.index) {
+            Some(existing) => *existing = channel,
+            None => self.channels.push(channel),
+        }
+        Ok(())
+    }
+
+    // 新定义的输入经过其他派生通道后又回到输出通道时形成循环，同一通道号的旧定义会被替换，不参与检查
+    fn forms_cycle(&self, channel: &DerivedChannel) -> bool {
+        let mut stack = channel.input_channels();
+        let mut visited = BTreeSet::new();
+        while let Some(input) = stack.pop() {
+            if input == channel.index {
+                return true;
+            }
+            if visited.insert(input)
+                && let Some(derived) = self.channels.iter().find(|c| c.index == input)
+            {
+                stack.extend(derived.input_channels());
+            }
+        }
+        false
+    }
+
+    // 某个通道收到了接收数据，同一通道号的派生通道停止计算，避免两种数据混在同一通道
+    pub fn mark_raw(&mut self, index: usize) {
+        if self.raw.insert(index)
+            && let Some(channel) = self.channels.iter_mut().find(|c| c.index == index)
+        {
+            channel.error = Some(format!("通道 {} 收到了接收数据，派生通道已停止计算，请修改输出通道", index));
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        self.channels.retain(|c| c.index != index);
+    }
+
+    // 清空数据后重新开始计算滤波器，重新记录哪些通道有接收数据
+    pub fn reset(&mut self) {
+        for channel in &mut self.channels {
+            channel.state = FilterState::default();
+            if self.raw.contains(&channel.index) {
+                channel.error = None;
+            }
+        }
+        self.updated.clear();
+        self.raw.clear();
+    }
+
+    // 某个通道收到新数据点：记录下来供表达式使用，并计算以它为输入的滤波通道
+    pub fn on_sample(&mut self, source: usize, sample: Sample) -> Vec<(usize, f64)> {
+        self.updated.entry(source).or_default().push(sample);
+        let raw = &self.raw;
+        self.channels
+            .iter_mut()
+            .filter(|c| c.kind.source() == Some(source) && c.index != source && !raw.contains(&c.index))
+            .filter_map(|c| Some((c.index, c.filter(sample)?)))
+            .collect()
+    }
+
+    // 直接或经过其他派生通道用到了 roots 中通道的派生通道
+    fn downstream(&self, roots: &BTreeSet<usize>) -> BTreeSet<usize> {
+        let mut found = BTreeSet::new();
+        loop {
+            let before = found.len();
+            for channel in &self.channels {
+                if channel.input_channels().iter().any(|i| roots.contains(i) || found.contains(i)) {
+                    found.insert(channel.index);
+                }
+            }
+            if found.len() == before {
+                return found;
+            }
+        }
+    }
+
+    // 现在可以计算的表达式通道：它引用的通道在本轮有新数据，并且不再等待其他表达式更新
+    // evaluated 为本轮已经计算过的表达式，表达式可以引用其他表达式或滤波通道，需要按依赖顺序计算
+    pub fn pending_expressions(&self, evaluated: &BTreeSet<usize>) -> Vec<usize> {
+        let is_pending = |c: &DerivedChannel| c.function.is_some() && !evaluated.contains(&c.index) && !self.raw.contains(&c.index);
+        let dirty = self.downstream(&self.updated.keys().copied().collect());
+        let unfinished: BTreeSet<usize> = self
+            .channels
+            .iter()
+            .filter(|c| is_pending(c) && dirty.contains(&c.index))
+            .map(|c| c.index)
+            .collect();
+        let waiting = self.downstream(&unfinished);
+        unfinished.into_iter().filter(|index| !waiting.contains(index)).collect()
+    }
+
+    pub fn finish_round(&mut self) {
+        self.updated.clear();
+    }
+
+    // 对本轮输入通道的每个新数据点计算一次表达式，返回 (驱动数据点, 输出值)
+    // 第 k 次计算使用各输入的第 k 个新数据点，新数据较少或没有新数据的输入沿用最新值，
+    // 输出的 X 和时间取自第一个在第 k 个位置有新数据的输入
+    pub fn evaluate(&mut self, index: usize, channels: &BTreeMap<usize, PlotChannel>) -> Vec<(Sample, f64)> {
+        let Some(channel) = self.channels.iter_mut().find(|c| c.index == index) else {
+            return Vec::new();
+        };
+        let (Some(function), Some(lua)) = (channel.function.as_ref(), self.lua.as_ref()) else {
+            return Vec::new();
+        };
+        // 还没有数据的输入通道不计算
+        let Some(latest) = channel
+            .inputs
+            .iter()
+            .map(|input| channels.get(input)?.data.last().copied())
+            .collect::<Option<Vec<Sample>>>()
+        else {
+            return Vec::new();
+        };
+        let new_samples: Vec<&[Sample]> = channel
+            .inputs
+            .iter()
+            .map(|input| self.updated.get(input).map_or(&[][..], Vec::as_slice))
+            .collect();
+        let rows = new_samples.iter().map(|samples| samples.len()).max().unwrap_or(0);
+
+        let globals = lua.globals();
+        let mut outputs = Vec::with_capacity(rows);
+        for k in 0..rows {
+            let mut driver = None;
+            for ((input, samples), latest) in channel.inputs.iter().zip(&new_samples).zip(&latest) {
+                let sample = samples.get(k).copied();
+                driver = driver.or(sample);
+                if let Err(e) = globals.set(format!("ch{}", input), sample.unwrap_or(*latest).y) {
+                    channel.error = Some(e.to_string());
+                    return outputs;
+                }
+            }
+            let Some(driver) = driver else {
+                continue;
+            };
+
+            self.instructions.set(0);
+            match function.call::<f64>(()) {
+                Ok(value) => {
+                    channel.error = None;
+                    outputs.push((driver, value));
+                }
+                Err(e) => {
+                    // 只保留第一行，不显示调用栈
+                    channel.error = e.to_string().lines().next().map(str::to_string);
+                    return outputs;
+                }
+            }
+        }
+        outputs
+    }
+}
+
+// 创建只开放数学函数的 Lua 环境，限制内存和每次求值的指令数
+fn create_expression_lua(instructions: Rc<Cell<u32>>) -> mlua::Result<Lua> {
+    let lua = Lua::new_with(StdLib::MATH, LuaOptions::default())?;
+    lua.set_memory_limit(EXPRESSION_MEMORY_LIMIT)?;
+    // 数学函数可以直接使用，如 sqrt(x)、abs(x)
+    // 去掉可以访问文件的基础函数，以及能捕获超时错误的 pcall
+    lua.load("for k, v in pairs(math) do _G[k] = v end dofile = nil loadfile = nil load = nil print = nil pcall = nil xpcall = nil")
+        .exec()?;
+
+    lua.set_hook(HookTriggers::new().every_nth_instruction(1000), move |_, _| {
+        instructions.set(instructions.get() + 1);
+        if instructions.get() > EXPRESSION_INSTRUCTION_LIMIT {
+            Err(mlua::Error::RuntimeError("表达式执行时间过长".to_string()))
+        } else {
+            Ok(VmState::Continue)
+        }
+    });
+    Ok(lua)
+}
+
+fn compile(lua: &Lua, channel: &mut DerivedChannel) {
+    if let DerivedKind::Expression(expression) = &channel.kind {
+        match lua.load(format!("return {}", expression)).set_name("表达式").into_function() {
+            Ok(function) => {
+                channel.function = Some(function);
+                channel.error = None;
+            }
+            Err(e) => channel.error = Some(e.to_string()),
+        }
+    }
+}
+
+// 从配置文件恢复派生通道，每行格式: 通道号<TAB>名称<TAB>类型<TAB>参数
+pub fn load_derived_settings(path: &str) -> Vec<DerivedChannel> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+
+    content
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.splitn(4, '\t').collect();
+            let [index, name, kind, params] = parts[..] else {
+                return None;
+            };
+            let index = index.parse().ok()?;
+            let params: Vec<&str> = params.split(',').map(str::trim).collect();
+            let source = || params.first()?.parse::<usize>().ok();
+            let kind = match kind {
+                "expr" => DerivedKind::Expression(parts[3].to_string()),
+                "avg" => DerivedKind::MovingAverage { source: source()?, window: params.get(1)?.parse().ok()? },
+                "lowpass" => DerivedKind::LowPass { source: source()?, alpha: params.get(1)?.parse().ok()? },
+                "diff" => DerivedKind::Derivative { source: source()? },
+                "integral" => DerivedKind::Integral { source: source()? },
+                _ => return None,
+            };
+            Some(DerivedChannel::new(index, name.to_string(), kind))
+        })
+        .collect()
+}
+
+pub fn save_derived_settings(path: &str, channels: &[DerivedChannel]) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    for channel in channels {
+        let (kind, params) = match &channel.kind {
+            DerivedKind::Expression(expression) => ("expr", expression.replace(['\t', '\n'], " ")),
+            DerivedKind::MovingAverage { source, window } => ("avg", format!("{},{}", source, window)),
+            DerivedKind::LowPass { source, alpha } => ("lowpass", format!("{},{}", source, alpha)),
+            DerivedKind::Derivative { source } => ("diff", format!("{}", source)),
+            DerivedKind::Integral { source } => ("integral", format!("{}", source)),
+        };
+        writeln!(file, "{}\t{}\t{}\t{}", channel.index, channel.name.replace('\t', " "), kind, params)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample_buffer::HistoryLimit;
+
+    fn expression(index: usize, expression: &str) -> DerivedChannel {
+        DerivedChannel::new(index, format!("ch{}", index), DerivedKind::Expression(expression.to_string()))
+    }
+
+    // 与 SerialAssistant::push_channel_sample 相同：添加数据点并计算滤波通道
+    fn push(derived: &mut DerivedChannels, channels: &mut BTreeMap<usize, PlotChannel>, index: usize, sample: Sample) {
+        let mut queue = vec![(index, sample)];
+        while let Some((index, sample)) = queue.pop() {
+            channels
+                .entry(index)
+                .or_insert_with(|| PlotChannel::new(index))
+                .data
+                .push(sample, HistoryLimit::Points(1000));
+            for (output, y) in derived.on_sample(index, sample) {
+                queue.push((output, Sample { y, ..sample }));
+            }
+        }
+    }
+
+    // 与 SerialAssistant::update_derived_channels 相同，返回表达式的计算顺序
+    fn finish_round(derived: &mut DerivedChannels, channels: &mut BTreeMap<usize, PlotChannel>) -> Vec<usize> {
+        let mut evaluated = BTreeSet::new();
+        let mut order = Vec::new();
+        loop {
+            let pending = derived.pending_expressions(&evaluated);
+            if pending.is_empty() {
+                break;
+            }
+            for index in pending {
+                evaluated.insert(index);
+                order.push(index);
+                for (driver, y) in derived.evaluate(index, channels) {
+                    push(derived, channels, index, Sample { y, ..driver });
+                }
+            }
+        }
+        derived.finish_round();
+        order
+    }
+
+    fn values(channels: &BTreeMap<usize, PlotChannel>, index: usize) -> Vec<(f64, f64)> {
+        channels[&index].data.iter().map(|s| (s.x, s.y)).collect()
+    }
+
+    #[test]
+    fn rejects_direct_and_indirect_cycles() {
+        let mut derived = DerivedChannels::new(Vec::new());
+        assert!(derived.add(expression(100, "ch100 + 1")).is_err());
+        assert!(derived.add(DerivedChannel::new(100, String::new(), DerivedKind::LowPass { source: 100, alpha: 0.5 })).is_err());
+
+        derived.add(expression(100, "ch101 * 2")).unwrap();
+        derived.add(DerivedChannel::new(101, String::new(), DerivedKind::MovingAverage { source: 102, window: 4 })).unwrap();
+        assert!(derived.add(expression(102, "ch0 + ch100")).is_err());
+        assert!(!derived.is_derived(102));
+        // 替换同一输出通道的旧定义时，旧定义的输入不参与检查
+        derived.add(expression(100, "ch0")).unwrap();
+        derived.add(expression(102, "ch0 + ch100")).unwrap();
+    }
+
+    #[test]
+    fn rejects_output_on_raw_channel() {
+        let mut derived = DerivedChannels::new(vec![expression(3, "ch0 * 2")]);
+        assert!(derived.error.is_none());
+        derived.mark_raw(3);
+        assert!(derived.channels[0].error.is_some());
+        assert!(derived.add(expression(3, "ch1")).is_err());
+        assert!(derived.pending_expressions(&BTreeSet::new()).is_empty());
+    }
+
+    #[test]
+    fn evaluates_in_dependency_order() {
+        // 定义顺序与依赖顺序相反: 102 依赖 101，101 依赖滤波通道 103
+        let mut derived = DerivedChannels::new(vec![
+            expression(102, "ch101 + ch1"),
+            expression(101, "ch103 * 2"),
+            DerivedChannel::new(103, String::new(), DerivedKind::LowPass { source: 0, alpha: 1.0 }),
+        ]);
+        assert!(derived.error.is_none());
+        let mut channels = BTreeMap::new();
+
+        push(&mut derived, &mut channels, 1, Sample { x: 0.0, y: 10.0, t: 0.0 });
+        push(&mut derived, &mut channels, 0, Sample { x: 0.0, y: 1.0, t: 0.0 });
+        assert_eq!(finish_round(&mut derived, &mut channels), vec![101, 102]);
+        assert_eq!(values(&channels, 101), vec![(0.0, 2.0)]);
+        assert_eq!(values(&channels, 102), vec![(0.0, 12.0)]);
+
+        // 只有 ch1 更新时 101 不需要计算
+        push(&mut derived, &mut channels, 1, Sample { x: 1.0, y: 20.0, t: 0.1 });
+        assert_eq!(finish_round(&mut derived, &mut channels), vec![102]);
+        assert_eq!(values(&channels, 102), vec![(0.0, 12.0), (1.0, 22.0)]);
+    }
+
+    #[test]
+    fn evaluates_each_sample_of_a_frame() {
+        let mut derived = DerivedChannels::new(vec![expression(100, "ch0 - ch1")]);
+        let mut channels = BTreeMap::new();
+
+        // 一帧中 ch0 有三个点，ch1 只有一个点，ch1 的值沿用到后面的点
+        for (x, y) in [(5.0, 1.0), (6.0, 2.0), (7.0, 3.0)] {
+            push(&mut derived, &mut channels, 0, Sample { x, y, t: 1.0 });
+        }
+        push(&mut derived, &mut channels, 1, Sample { x: 5.0, y: 0.5, t: 1.0 });
+        finish_round(&mut derived, &mut channels);
+        assert_eq!(values(&channels, 100), vec![(5.0, 0.5), (6.0, 1.5), (7.0, 2.5)]);
+
+        // 没有新数据的输入使用最新值
+        push(&mut derived, &mut channels, 1, Sample { x: 8.0, y: 1.0, t: 2.0 });
+        finish_round(&mut derived, &mut channels);
+        assert_eq!(values(&channels, 100).last(), Some(&(8.0, 2.0)));
+    }
+
+    #[test]
+    fn waits_for_all_inputs() {
+        let mut derived = DerivedChannels::new(vec![expression(100, "ch0 + ch1")]);
+        let mut channels = BTreeMap::new();
+        push(&mut derived, &mut channels, 0, Sample { x: 0.0, y: 1.0, t: 0.0 });
+        finish_round(&mut derived, &mut channels);
+        assert!(!channels.contains_key(&100));
+    }
+}
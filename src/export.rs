@@ -0,0 +1,237 @@
+use crate::channel::PlotChannel;
+use crate::sample_buffer::{Sample, XAxisMode};
+use crate::wave_ui::format_x;
+use chrono::{DateTime, Local};
+use egui::{ColorImage, Rect};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+// SVG 图片的尺寸和边距
+const SVG_WIDTH: f64 = 1200.0;
+const SVG_HEIGHT: f64 = 600.0;
+const SVG_MARGIN: [f64; 4] = [80.0, 20.0, 20.0, 50.0]; // 左、右、上、下
+
+// 通道在导出范围内的数据，window 为 None 时导出全部历史
+fn samples_in<'a>(channel: &'a PlotChannel, window: Option<[f64; 2]>, mode: XAxisMode) -> Box<dyn Iterator<Item = &'a Sample> + 'a> {
+    match window {
+        Some([min_x, max_x]) => Box::new(
+            channel
+                .data
+                .range(channel.data.index_range(min_x, max_x, mode))
+                .filter(move |s| (min_x..=max_x).contains(&s.x_on(mode))),
+        ),
+        None => Box::new(channel.data.iter()),
+    }
+}
+
+// 导出为 CSV，同一帧收到的各通道数据合并为一行，没有数据的通道留空
+pub fn export_csv(
+    path: &Path,
+    channels: &[(usize, &PlotChannel)],
+    window: Option<[f64; 2]>,
+    mode: XAxisMode,
+    epoch: DateTime<Local>,
+) -> std::io::Result<usize> {
+    let columns = channels
+        .iter()
+        .map(|(index, channel)| (*index, channel.label(), samples_in(channel, window, mode)))
+        .collect();
+    write_csv(path, columns, epoch)
+}
+
+// 数据点所在的帧：同一帧的数据到达时间相同，按写入文件的微秒精度比较，避免浮点误差把一帧拆成多行
+fn frame_key(sample: &Sample) -> i64 {
+    (sample.t * 1e6).round() as i64
+}
+
+// 每列为 (通道号, 名称, 按时间排序的数据点)，每个通道写数值和横坐标两列，返回写入的行数
+pub fn write_csv<'a>(path: &Path, columns: Vec<(usize, String, Box<dyn Iterator<Item = &'a Sample> + 'a>)>, epoch: DateTime<Local>) -> std::io::Result<usize> {
+    let mut file = BufWriter::new(std::fs::File::create(path)?);
+    let mut header = vec!["index".to_string(), "time_s".to_string(), "timestamp".to_string()];
+    for (index, label, _) in &columns {
+        header.push(format!("ch{} {}", index, label.replace([',', '"'], " ")));
+        header.push(format!("x_ch{}", index));
+    }
+    writeln!(file, "{}", header.join(","))?;
+
+    let mut cursors: Vec<_> = columns.into_iter().map(|(_, _, samples)| samples.peekable()).collect();
+    let mut rows = 0;
+    while let Some(key) = cursors.iter_mut().filter_map(|c| c.peek().map(|s| frame_key(s))).min() {
+        let t = key as f64 / 1e6;
+        let timestamp = epoch + chrono::Duration::microseconds(key);
+        write!(file, "{},{:.6},{}", rows, t, timestamp.format("%Y-%m-%d %H:%M:%S%.6f"))?;
+        for cursor in &mut cursors {
+            match cursor.next_if(|s| frame_key(s) == key) {
+                Some(sample) => write!(file, ",{},{}", sample.y, sample.x)?,
+                None => write!(file, ",,")?,
+            }
+        }
+        writeln!(file)?;
+        rows += 1;
+    }
+    file.flush()?;
+    Ok(rows)
+}
+
+// 从窗口截图中裁剪波形区域保存为 PNG
+pub fn save_png(path: &Path, image: &ColorImage, region: Rect, pixels_per_point: f32) -> image::ImageResult<()> {
+    let full = Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(image.width() as f32, image.height() as f32) / pixels_per_point);
+    let cropped = image.region(&region.intersect(full), Some(pixels_per_point));
+    image::save_buffer(
+        path,
+        cropped.as_raw(),
+        cropped.width() as u32,
+        cropped.height() as u32,
+        image::ExtendedColorType::Rgba8,
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// 把可见范围内的模拟通道绘制为矢量图，纵轴按数据自动缩放
+pub fn export_svg(
+    path: &Path,
+    channels: &[&PlotChannel],
+    window: [f64; 2],
+    mode: XAxisMode,
+    epoch: DateTime<Local>,
+) -> std::io::Result<()> {
+    let [left, right, top, bottom] = SVG_MARGIN;
+    let (plot_w, plot_h) = (SVG_WIDTH - left - right, SVG_HEIGHT - top - bottom);
+
+    let traces: Vec<(&PlotChannel, Vec<[f64; 2]>)> = channels
+        .iter()
+        .map(|channel| {
+            let range = channel.data.index_range(window[0], window[1], mode);
+            let points = channel
+                .data
+                .decimate(range, plot_w as usize, mode)
+                .into_iter()
+                .map(|[x, y]| [x, channel.scaled(y)])
+                .collect();
+            (*channel, points)
+        })
+        .collect();
+
+    let (mut min_y, mut max_y) = traces
+        .iter()
+        .flat_map(|(_, points)| points.iter().map(|p| p[1]))
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), y| (min.min(y), max.max(y)));
+    if !min_y.is_finite() {
+        (min_y, max_y) = (0.0, 1.0);
+    } else if max_y <= min_y {
+        (min_y, max_y) = (min_y - 0.5, max_y + 0.5);
+    }
+    let pad = (max_y - min_y) * 0.05;
+    let (min_y, max_y) = (min_y - pad, max_y + pad);
+    let span_x = if window[1] > window[0] { window[1] - window[0] } else { 1.0 };
+
+    let sx = |x: f64| left + (x - window[0]) / span_x * plot_w;
+    let sy = |y: f64| top + (max_y - y) / (max_y - min_y) * plot_h;
+
+    let mut file = BufWriter::new(std::fs::File::create(path)?);
+    writeln!(
+        file,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="12">"#,
+        w = SVG_WIDTH,
+        h = SVG_HEIGHT
+    )?;
+    writeln!(file, r#"<rect width="100%" height="100%" fill="white"/>"#)?;
+    writeln!(file, r#"<clipPath id="plot"><rect x="{left}" y="{top}" width="{plot_w}" height="{plot_h}"/></clipPath>"#)?;
+
+    // 网格和刻度
+    const TICKS: usize = 5;
+    for i in 0..=TICKS {
+        let fraction = i as f64 / TICKS as f64;
+        let x = left + fraction * plot_w;
+        let y = top + fraction * plot_h;
+        writeln!(file, r##"<line x1="{x:.1}" y1="{top}" x2="{x:.1}" y2="{:.1}" stroke="#ddd"/>"##, top + plot_h)?;
+        writeln!(file, r##"<line x1="{left}" y1="{y:.1}" x2="{:.1}" y2="{y:.1}" stroke="#ddd"/>"##, left + plot_w)?;
+        let x_label = format_x(window[0] + fraction * span_x, mode, epoch);
+        writeln!(file, r#"<text x="{x:.1}" y="{:.1}" text-anchor="middle">{}</text>"#, top + plot_h + 18.0, escape(&x_label))?;
+        let y_value = max_y - fraction * (max_y - min_y);
+        writeln!(file, r#"<text x="{:.1}" y="{:.1}" text-anchor="end">{:.4}</text>"#, left - 6.0, y + 4.0, y_value)?;
+    }
+    writeln!(file, r#"<rect x="{left}" y="{top}" width="{plot_w}" height="{plot_h}" fill="none" stroke="black"/>"#)?;
+    writeln!(file, r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}</text>"#, left + plot_w / 2.0, SVG_HEIGHT - 8.0, escape(mode.label()))?;
+
+    // 曲线和图例
+    for (i, (channel, points)) in traces.iter().enumerate() {
+        let color = format!("#{:02x}{:02x}{:02x}", channel.color.r(), channel.color.g(), channel.color.b());
+        let coordinates: Vec<String> = points.iter().map(|[x, y]| format!("{:.2},{:.2}", sx(*x), sy(*y))).collect();
+        writeln!(file, r#"<polyline clip-path="url(#plot)" fill="none" stroke="{}" stroke-width="1.5" points="{}"/>"#, color, coordinates.join(" "))?;
+
+        let legend_y = top + 16.0 + i as f64 * 16.0;
+        let legend_x = left + plot_w - 160.0;
+        writeln!(file, r#"<line x1="{legend_x:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{color}" stroke-width="3"/>"#, legend_y - 4.0, legend_x + 20.0, legend_y - 4.0)?;
+        writeln!(file, r#"<text x="{:.1}" y="{legend_y:.1}">{}</text>"#, legend_x + 26.0, escape(&channel.plot_label()))?;
+    }
+    writeln!(file, "</svg>")?;
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::read_csv;
+    use crate::sample_buffer::HistoryLimit;
+    use chrono::TimeZone;
+
+    fn channel(index: usize, name: &str, unit: &str, samples: &[(f64, f64, f64)]) -> PlotChannel {
+        let mut channel = PlotChannel::new(index);
+        channel.name = name.to_string();
+        channel.unit = unit.to_string();
+        for &(x, y, t) in samples {
+            channel.data.push(Sample { x, y, t }, HistoryLimit::Points(1000));
+        }
+        channel
+    }
+
+    #[test]
+    fn csv_round_trip_keeps_frames_and_x() {
+        // 第一帧 ch0 有三个点，两个通道各有只出现在部分帧的数据，最后一帧两个通道的时间有浮点误差
+        let ch0 = channel(0, "温度", "℃", &[(1.0, 20.5, 0.1), (2.0, 20.75, 0.1), (3.0, 21.0, 0.1), (4.0, 21.25, 0.2), (7.0, 1.0, 0.4000000001)]);
+        let ch3 = channel(3, "电流", "", &[(10.0, -1.5, 0.1), (11.0, 0.125, 0.3), (12.5, 2.0, 0.4)]);
+        let path = std::env::temp_dir().join(format!("export_round_trip_{}.csv", std::process::id()));
+        let epoch = Local.with_ymd_and_hms(2026, 3, 4, 5, 6, 7).unwrap();
+        let rows = export_csv(&path, &[(0, &ch0), (3, &ch3)], None, XAxisMode::Index, epoch).unwrap();
+        let csv = read_csv(&path);
+        let _ = std::fs::remove_file(&path);
+        let csv = csv.unwrap();
+
+        assert_eq!(csv.channels, vec![(0, "温度".to_string()), (3, "电流".to_string())]);
+        let values: Vec<Vec<(usize, Option<f64>, f64)>> =
+            csv.rows.iter().map(|row| row.values.iter().map(|v| (v.channel, v.x, v.y)).collect()).collect();
+        assert_eq!(
+            values,
+            vec![
+                vec![(0, Some(1.0), 20.5), (3, Some(10.0), -1.5)],
+                vec![(0, Some(2.0), 20.75)],
+                vec![(0, Some(3.0), 21.0)],
+                vec![(0, Some(4.0), 21.25)],
+                vec![(3, Some(11.0), 0.125)],
+                vec![(0, Some(7.0), 1.0), (3, Some(12.5), 2.0)],
+            ]
+        );
+        assert_eq!(rows, values.len());
+
+        let times: Vec<Option<f64>> = csv.rows.iter().map(|row| row.t).collect();
+        assert_eq!(times, [0.1, 0.1, 0.1, 0.2, 0.3, 0.4].map(Some));
+        let offset = csv.rows[3].time.unwrap() - epoch;
+        assert_eq!(offset.num_microseconds(), Some(200_000));
+    }
+
+    #[test]
+    fn csv_exports_only_window() {
+        let ch0 = channel(0, "a", "", &[(1.0, 1.0, 0.1), (2.0, 2.0, 0.2), (3.0, 3.0, 0.3), (4.0, 4.0, 0.4)]);
+        let path = std::env::temp_dir().join(format!("export_window_{}.csv", std::process::id()));
+        let rows = export_csv(&path, &[(0, &ch0)], Some([0.15, 0.3]), XAxisMode::Relative, Local::now()).unwrap();
+        let csv = read_csv(&path);
+        let _ = std::fs::remove_file(&path);
+        let x: Vec<Option<f64>> = csv.unwrap().rows.iter().map(|row| row.values[0].x).collect();
+        assert_eq!(rows, 2);
+        assert_eq!(x, vec![Some(2.0), Some(3.0)]);
+    }
+}
@@ -0,0 +1,260 @@
+use crate::channel::PlotChannel;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+// 频谱刷新间隔
+const SPECTRUM_INTERVAL: Duration = Duration::from_millis(100);
+
+// 可选的 FFT 点数
+pub const FFT_SIZES: [usize; 9] = [256, 512, 1024, 2048, 4096, 8192, 16384, 32768, 65536];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FftWindow {
+    Rectangular,
+    Hann,
+    Blackman,
+}
+
+impl FftWindow {
+    pub const ALL: [FftWindow; 3] = [FftWindow::Rectangular, FftWindow::Hann, FftWindow::Blackman];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FftWindow::Rectangular => "矩形窗",
+            FftWindow::Hann => "汉宁窗",
+            FftWindow::Blackman => "布莱克曼窗",
+        }
+    }
+
+    fn coefficient(&self, i: usize, n: usize) -> f64 {
+        let phase = 2.0 * std::f64::consts::PI * i as f64 / (n - 1).max(1) as f64;
+        match self {
+            FftWindow::Rectangular => 1.0,
+            FftWindow::Hann => 0.5 - 0.5 * phase.cos(),
+            FftWindow::Blackman => 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos(),
+        }
+    }
+}
+
+// 原地基 2 FFT，长度必须是 2 的幂
+pub fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+    debug_assert!(n.is_power_of_two() && im.len() == n);
+
+    // 位反转重排
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * std::f64::consts::PI / len as f64;
+        let (w_re, w_im) = (angle.cos(), angle.sin());
+        for start in (0..n).step_by(len) {
+            let (mut cur_re, mut cur_im) = (1.0, 0.0);
+            for k in 0..len / 2 {
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * cur_re - im[b] * cur_im;
+                let t_im = re[b] * cur_im + im[b] * cur_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+                let next_re = cur_re * w_re - cur_im * w_im;
+                cur_im = cur_re * w_im + cur_im * w_re;
+                cur_re = next_re;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+// 单边幅度谱，幅度按窗函数的增益修正，正弦波的峰值等于其幅度
+pub fn amplitude_spectrum(values: &[f64], window: FftWindow) -> Vec<f64> {
+    let n = values.len();
+    let coefficients: Vec<f64> = (0..n).map(|i| window.coefficient(i, n)).collect();
+    let gain: f64 = coefficients.iter().sum();
+    let mut re: Vec<f64> = values.iter().zip(&coefficients).map(|(v, w)| v * w).collect();
+    let mut im = vec![0.0; n];
+    fft(&mut re, &mut im);
+
+    (0..=n / 2)
+        .map(|k| {
+            let magnitude = (re[k] * re[k] + im[k] * im[k]).sqrt() / gain;
+            if k == 0 || k == n / 2 { magnitude } else { magnitude * 2.0 }
+        })
+        .collect()
+}
+
+pub struct SpectrumResult {
+    pub points: Vec<[f64; 2]>, // (频率, 幅度)，dB 模式下幅度已换算
+    pub peak: Option<[f64; 2]>, // 除直流外的最大分量
+    pub sample_rate: f64,
+    pub size: usize,
+}
+
+// 频谱面板：对选中通道最近 N 个点做 FFT
+pub struct Spectrum {
+    pub show: bool,
+    pub channel: usize,
+    pub size: usize,
+    pub window: FftWindow,
+    pub db: bool,
+    pub auto_rate: bool, // 按数据到达时间估算采样率
+    pub sample_rate: f64,
+    pub result: Option<SpectrumResult>,
+    last_update: Option<Instant>,
+}
+
+impl Default for Spectrum {
+    fn default() -> Self {
+        Self {
+            show: false,
+            channel: 0,
+            size: 1024,
+            window: FftWindow::Hann,
+            db: false,
+            auto_rate: true,
+            sample_rate: 1000.0,
+            result: None,
+            last_update: None,
+        }
+    }
+}
+
+impl Spectrum {
+    pub fn invalidate(&mut self) {
+        self.last_update = None;
+    }
+
+    pub fn update(&mut self, channels: &BTreeMap<usize, PlotChannel>) {
+        if !self.show || self.last_update.is_some_and(|t| t.elapsed() < SPECTRUM_INTERVAL) {
+            return;
+        }
+        self.last_update = Some(Instant::now());
+        self.result = None;
+
+        let Some(channel) = channels.get(&self.channel) else {
+            return;
+        };
+        // 数据不足时使用不超过现有点数的最大 2 的幂
+        let available = channel.data.len().min(self.size);
+        if available < 8 {
+            return;
+        }
+        let size = if available.is_power_of_two() { available } else { available.next_power_of_two() / 2 };
+        let start = channel.data.len() - size;
+        let samples: Vec<_> = channel.data.range(start..channel.data.len()).copied().collect();
+
+        let sample_rate = if self.auto_rate {
+            let duration = samples[size - 1].t - samples[0].t;
+            if duration <= 0.0 {
+                return;
+            }
+            (size - 1) as f64 / duration
+        } else {
+            self.sample_rate
+        };
+
+        let values: Vec<f64> = samples.iter().map(|s| s.y).collect();
+        let spectrum = amplitude_spectrum(&values, self.window);
+        let resolution = sample_rate / size as f64;
+
+        let peak = spectrum
+            .iter()
+            .enumerate()
+            .skip(1)
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(k, magnitude)| [k as f64 * resolution, *magnitude]);
+        let scale = |magnitude: f64| if self.db { 20.0 * magnitude.max(1e-12).log10() } else { magnitude };
+
+        self.result = Some(SpectrumResult {
+            points: spectrum.iter().enumerate().map(|(k, m)| [k as f64 * resolution, scale(*m)]).collect(),
+            peak: peak.map(|[f, m]| [f, scale(m)]),
+            sample_rate,
+            size,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(n: usize, cycles: f64, amplitude: f64, dc: f64) -> Vec<f64> {
+        (0..n)
+            .map(|i| dc + amplitude * (2.0 * std::f64::consts::PI * cycles * i as f64 / n as f64 + 0.3).sin())
+            .collect()
+    }
+
+    fn peak(spectrum: &[f64]) -> (usize, f64) {
+        spectrum
+            .iter()
+            .copied()
+            .enumerate()
+            .skip(1)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap()
+    }
+
+    #[test]
+    fn fft_matches_dft() {
+        let n = 16;
+        let values: Vec<f64> = (0..n).map(|i| ((i * 7) % 5) as f64 - 1.5).collect();
+        let mut re = values.clone();
+        let mut im = vec![0.0; n];
+        fft(&mut re, &mut im);
+        for k in 0..n {
+            let (mut dft_re, mut dft_im) = (0.0, 0.0);
+            for (i, v) in values.iter().enumerate() {
+                let angle = -2.0 * std::f64::consts::PI * (k * i) as f64 / n as f64;
+                dft_re += v * angle.cos();
+                dft_im += v * angle.sin();
+            }
+            assert!((re[k] - dft_re).abs() < 1e-9 && (im[k] - dft_im).abs() < 1e-9, "{}", k);
+        }
+    }
+
+    #[test]
+    fn sine_peak_at_bin_with_amplitude() {
+        for window in FftWindow::ALL {
+            for n in [256, 1024, 4096] {
+                let spectrum = amplitude_spectrum(&sine(n, 37.0, 3.0, 0.5), window);
+                assert_eq!(spectrum.len(), n / 2 + 1);
+                let (bin, amplitude) = peak(&spectrum);
+                assert_eq!(bin, 37, "{:?} {}", window, n);
+                assert!((amplitude - 3.0).abs() < 0.03, "{:?} {} {}", window, n, amplitude);
+                assert!((spectrum[0] - 0.5).abs() < 0.01, "{:?} {} {}", window, n, spectrum[0]);
+            }
+        }
+    }
+
+    #[test]
+    fn off_bin_sine_stays_near_its_bin() {
+        // 频率落在两个频点之间时，峰值在相邻频点上，幅度损失不超过窗函数的扇贝损失
+        for (window, min_ratio) in [(FftWindow::Rectangular, 0.63), (FftWindow::Hann, 0.84), (FftWindow::Blackman, 0.88)] {
+            let spectrum = amplitude_spectrum(&sine(1024, 100.5, 2.0, 0.0), window);
+            let (bin, amplitude) = peak(&spectrum);
+            assert!(bin == 100 || bin == 101, "{:?} {}", window, bin);
+            assert!(amplitude > 2.0 * min_ratio && amplitude <= 2.0 * 1.01, "{:?} {}", window, amplitude);
+        }
+    }
+
+    #[test]
+    fn nyquist_bin_is_not_doubled() {
+        let values: Vec<f64> = (0..64).map(|i| if i % 2 == 0 { 1.0 } else { -1.0 }).collect();
+        let spectrum = amplitude_spectrum(&values, FftWindow::Rectangular);
+        assert!((spectrum[32] - 1.0).abs() < 1e-9);
+        assert!(spectrum[..32].iter().all(|m| m.abs() < 1e-9));
+    }
+}
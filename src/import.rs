@@ -0,0 +1,215 @@
+use crate::serial::RxChunk;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+
+// CSV 中一个通道的数据，x 来自本程序导出的 x_chN 列
+pub struct CsvValue {
+    pub channel: usize,
+    pub x: Option<f64>,
+    pub y: f64,
+}
+
+// CSV 中的一行
+pub struct CsvRow {
+    pub t: Option<f64>,                 // time_s 列
+    pub time: Option<DateTime<Local>>,  // timestamp 列
+    pub values: Vec<CsvValue>,
+}
+
+pub struct CsvImport {
+    pub channels: Vec<(usize, String)>, // 表头中的通道号和名称
+    pub rows: Vec<CsvRow>,
+}
+
+fn parse_time(text: &str) -> Option<DateTime<Local>> {
+    let naive = NaiveDateTime::parse_from_str(text.trim(), "%Y-%m-%d %H:%M:%S%.f").ok()?;
+    Local.from_local_datetime(&naive).single()
+}
+
+// 表头 "ch3 温度 (℃)" 使用通道号 3 和名称 "温度"，否则按列顺序编号
+fn parse_channel_header(header: &str, column: usize) -> (usize, String) {
+    let header = header.trim().trim_matches('"');
+    if let Some(rest) = header.strip_prefix("ch") {
+        let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if let Ok(index) = digits.parse() {
+            let name = rest[digits.len()..].trim();
+            // 导出时名称后附带了单位
+            let name = match name.rfind(" (") {
+                Some(pos) if name.ends_with(')') => &name[..pos],
+                _ => name,
+            };
+            let name = if name.is_empty() { format!("通道 {}", index) } else { name.to_string() };
+            return (index, name);
+        }
+    }
+    let name = if header.is_empty() { format!("通道 {}", column) } else { header.to_string() };
+    (column, name)
+}
+
+// 读取 CSV，支持本程序导出的格式，也支持逗号、分号或制表符分隔的普通数值表格
+pub fn read_csv(path: &std::path::Path) -> Result<CsvImport, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty()).peekable();
+    let first = *lines.peek().ok_or("文件为空")?;
+    let delimiter = if first.contains('\t') {
+        '\t'
+    } else if first.contains(';') && !first.contains(',') {
+        ';'
+    } else {
+        ','
+    };
+
+    // 第一行全是数字时没有表头
+    let has_header = first.split(delimiter).any(|cell| !cell.trim().is_empty() && cell.trim().parse::<f64>().is_err());
+    let headers: Vec<String> = if has_header {
+        lines.next().unwrap_or_default().split(delimiter).map(|s| s.trim().to_string()).collect()
+    } else {
+        (0..first.split(delimiter).count()).map(|i| format!("ch{}", i)).collect()
+    };
+
+    let mut t_column = None;
+    let mut time_column = None;
+    let mut channels = Vec::new();
+    let mut data_columns = Vec::new();
+    let mut x_columns: Vec<(usize, usize)> = Vec::new(); // (列, 通道号)
+    for (column, header) in headers.iter().enumerate() {
+        let lower = header.to_lowercase();
+        if let Some(channel) = lower.strip_prefix("x_ch").and_then(|n| n.parse().ok()) {
+            x_columns.push((column, channel));
+            continue;
+        }
+        match lower.as_str() {
+            "index" => {}
+            "time_s" | "time" | "t" => t_column = Some(column),
+            "timestamp" => time_column = Some(column),
+            _ => {
+                let channel = parse_channel_header(header, data_columns.len());
+                data_columns.push((column, channel.0));
+                channels.push(channel);
+            }
+        }
+    }
+    if channels.is_empty() {
+        return Err("没有找到数据列".to_string());
+    }
+
+    let rows = lines
+        .map(|line| {
+            let cells: Vec<&str> = line.split(delimiter).collect();
+            let cell = |column: usize| cells.get(column).map(|c| c.trim()).filter(|c| !c.is_empty());
+            CsvRow {
+                t: t_column.and_then(cell).and_then(|c| c.parse().ok()),
+                time: time_column.and_then(cell).and_then(parse_time),
+                values: data_columns
+                    .iter()
+                    .filter_map(|(column, channel)| {
+                        let x_column = x_columns.iter().find(|(_, c)| c == channel).map(|(column, _)| *column);
+                        Some(CsvValue {
+                            channel: *channel,
+                            x: x_column.and_then(cell).and_then(|c| c.parse().ok()),
+                            y: cell(*column)?.parse().ok()?,
+                        })
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    Ok(CsvImport { channels, rows })
+}
+
+// 读取接收日志中的 RX 数据，格式为 "[时间] RX: 十六进制"，每 16 个字节换行
+pub fn read_capture_log(path: &std::path::Path) -> Result<Vec<RxChunk>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut chunks: Vec<RxChunk> = Vec::new();
+    // 当前行属于 RX 十六进制数据时，后续不带时间戳的行是它的续行
+    let mut in_hex = false;
+
+    for line in content.lines() {
+        if let Some(rest) = line.strip_prefix('[')
+            && let Some((time, record)) = rest.split_once("] ")
+        {
+            in_hex = false;
+            let Some(hex) = record.strip_prefix("RX: ") else {
+                continue;
+            };
+            if hex.starts_with("raw_data:") {
+                continue;
+            }
+            let time = parse_time(time).ok_or_else(|| format!("无法识别的时间: {}", time))?;
+            chunks.push(RxChunk { time, data: crate::utils::hex_to_bytes(hex)? });
+            in_hex = true;
+        } else if in_hex && let Some(chunk) = chunks.last_mut() {
+            chunk.data.extend(crate::utils::hex_to_bytes(line)?);
+        }
+    }
+
+    if chunks.is_empty() {
+        return Err("没有找到接收数据(RX)记录".to_string());
+    }
+    Ok(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(name: &str, content: &str) -> Result<CsvImport, String> {
+        let path = std::env::temp_dir().join(format!("import_{}_{}.csv", name, std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        let csv = read_csv(&path);
+        let _ = std::fs::remove_file(&path);
+        csv
+    }
+
+    fn values(csv: &CsvImport) -> Vec<Vec<(usize, Option<f64>, f64)>> {
+        csv.rows.iter().map(|row| row.values.iter().map(|v| (v.channel, v.x, v.y)).collect()).collect()
+    }
+
+    #[test]
+    fn reads_exported_csv_with_x_columns() {
+        let csv = read(
+            "exported",
+            "index,time_s,timestamp,ch2 速度 (m/s),x_ch2,ch5 ,x_ch5\n\
+             0,0.100000,2026-03-04 05:06:07.100000,1.5,10,,\n\
+             1,0.100000,2026-03-04 05:06:07.100000,2.5,11,-3,0.25\n\
+             2,0.250000,2026-03-04 05:06:07.250000,,,4,\n",
+        )
+        .unwrap();
+        assert_eq!(csv.channels, vec![(2, "速度".to_string()), (5, "通道 5".to_string())]);
+        assert_eq!(
+            values(&csv),
+            vec![
+                vec![(2, Some(10.0), 1.5)],
+                vec![(2, Some(11.0), 2.5), (5, Some(0.25), -3.0)],
+                // X 为空时由通道按采样序号编号
+                vec![(5, None, 4.0)],
+            ]
+        );
+        assert_eq!(csv.rows.iter().map(|row| row.t).collect::<Vec<_>>(), vec![Some(0.1), Some(0.1), Some(0.25)]);
+        assert!(csv.rows.iter().all(|row| row.time.is_some()));
+    }
+
+    #[test]
+    fn reads_x_column_before_its_channel() {
+        let csv = read("x_first", "x_ch1;ch1;time\n5;1;0.5\n6;2;0.6\n").unwrap();
+        assert_eq!(csv.channels, vec![(1, "通道 1".to_string())]);
+        assert_eq!(values(&csv), vec![vec![(1, Some(5.0), 1.0)], vec![(1, Some(6.0), 2.0)]]);
+        assert_eq!(csv.rows[1].t, Some(0.6));
+    }
+
+    #[test]
+    fn reads_plain_table_without_x() {
+        let csv = read("plain", "1\t2\n3\t4\n").unwrap();
+        assert_eq!(csv.channels, vec![(0, "通道 0".to_string()), (1, "通道 1".to_string())]);
+        assert_eq!(values(&csv), vec![vec![(0, None, 1.0), (1, None, 2.0)], vec![(0, None, 3.0), (1, None, 4.0)]]);
+        assert!(csv.rows.iter().all(|row| row.t.is_none() && row.time.is_none()));
+
+        let csv = read("named", "电压,电流\n1,2\n").unwrap();
+        assert_eq!(csv.channels, vec![(0, "电压".to_string()), (1, "电流".to_string())]);
+    }
+
+    #[test]
+    fn rejects_files_without_data() {
+        assert!(read("empty", "\n\n").is_err());
+        assert!(read("no_channels", "index,time_s,x_ch0\n0,0.1,1\n").is_err());
+    }
+}
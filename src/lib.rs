@@ -3,4 +3,21 @@ pub mod serial;
 pub mod ui;
 pub mod utils;
 pub mod frame_history;
+pub mod script;
+pub mod channel;
+pub mod sample_buffer;
+pub mod wave_ui;
+pub mod trigger;
+pub mod measure;
+pub mod fft;
+pub mod derived;
+pub mod xy;
+pub mod alarm;
+pub mod dashboard;
+pub mod dashboard_ui;
+pub mod export;
+pub mod import;
+pub mod reference;
+pub mod receive_buffer;
+pub mod receive_view;
 pub use app::SerialAssistant;
\ No newline at end of file
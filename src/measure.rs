@@ -0,0 +1,307 @@
+use crate::channel::PlotChannel;
+use crate::sample_buffer::{Sample, XAxisMode};
+use egui::Color32;
+use egui_plot::{HLine, LineStyle, PlotBounds, PlotPoint, PlotUi, VLine};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+// 鼠标距离光标线多少像素以内可以拖动
+const GRAB_DISTANCE: f32 = 6.0;
+// 统计结果刷新间隔，历史数据很长时避免每帧遍历
+const STATS_INTERVAL: Duration = Duration::from_millis(200);
+
+const CURSOR_COLOR: Color32 = Color32::from_rgb(255, 165, 0);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CursorLine {
+    X1,
+    X2,
+    Y1,
+    Y2,
+}
+
+// 测量光标：两条竖线和两条横线，拖动测量时间差、频率和幅度差
+pub struct Cursors {
+    pub enabled: bool,
+    pub x: [f64; 2],
+    pub y: [f64; 2],
+    placed: bool,
+    hovered: Option<CursorLine>,
+    dragging: Option<CursorLine>,
+}
+
+impl Default for Cursors {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            x: [0.0; 2],
+            y: [0.0; 2],
+            placed: false,
+            hovered: None,
+            dragging: None,
+        }
+    }
+}
+
+impl Cursors {
+    // 鼠标在光标线上时由光标处理拖动，波形不跟随平移
+    pub fn wants_pointer(&self) -> bool {
+        self.enabled && (self.hovered.is_some() || self.dragging.is_some())
+    }
+
+    // 下一次显示时把光标放回当前视图的 1/3 和 2/3 处
+    pub fn reset(&mut self) {
+        self.placed = false;
+    }
+
+    fn place(&mut self, bounds: &PlotBounds) {
+        let (min, max) = (bounds.min(), bounds.max());
+        self.x = [min[0] + (max[0] - min[0]) / 3.0, min[0] + (max[0] - min[0]) * 2.0 / 3.0];
+        self.y = [min[1] + (max[1] - min[1]) / 3.0, min[1] + (max[1] - min[1]) * 2.0 / 3.0];
+        self.placed = true;
+    }
+
+    fn value_mut(&mut self, line: CursorLine) -> &mut f64 {
+        match line {
+            CursorLine::X1 => &mut self.x[0],
+            CursorLine::X2 => &mut self.x[1],
+            CursorLine::Y1 => &mut self.y[0],
+            CursorLine::Y2 => &mut self.y[1],
+        }
+    }
+
+    // 在波形中绘制光标并处理拖动
+    pub fn show(&mut self, plot_ui: &mut PlotUi) {
+        if !self.enabled {
+            return;
+        }
+        if !self.placed {
+            self.place(&plot_ui.plot_bounds());
+        }
+
+        // 不使用 pointer_coordinate，它在拖动时会减去波形平移的距离
+        let pointer = plot_ui
+            .ctx()
+            .input(|i| i.pointer.latest_pos())
+            .map(|pos| (pos, plot_ui.plot_from_screen(pos)));
+
+        if self.dragging.is_none() {
+            self.hovered = None;
+            if let Some((pos, _)) = pointer.filter(|_| plot_ui.response().hovered()) {
+                let mut nearest = GRAB_DISTANCE;
+                for (line, distance) in [
+                    (CursorLine::X1, (plot_ui.screen_from_plot(PlotPoint::new(self.x[0], 0.0)).x - pos.x).abs()),
+                    (CursorLine::X2, (plot_ui.screen_from_plot(PlotPoint::new(self.x[1], 0.0)).x - pos.x).abs()),
+                    (CursorLine::Y1, (plot_ui.screen_from_plot(PlotPoint::new(0.0, self.y[0])).y - pos.y).abs()),
+                    (CursorLine::Y2, (plot_ui.screen_from_plot(PlotPoint::new(0.0, self.y[1])).y - pos.y).abs()),
+                ] {
+                    if distance < nearest {
+                        nearest = distance;
+                        self.hovered = Some(line);
+                    }
+                }
+            }
+        }
+
+        if plot_ui.response().drag_started() {
+            self.dragging = self.hovered;
+        }
+        if !plot_ui.response().dragged() {
+            self.dragging = None;
+        }
+        if let (Some(line), Some((_, value))) = (self.dragging, pointer) {
+            *self.value_mut(line) = match line {
+                CursorLine::X1 | CursorLine::X2 => value.x,
+                CursorLine::Y1 | CursorLine::Y2 => value.y,
+            };
+        }
+
+        let active = self.dragging.or(self.hovered);
+        match active {
+            Some(CursorLine::X1 | CursorLine::X2) => plot_ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeHorizontal),
+            Some(CursorLine::Y1 | CursorLine::Y2) => plot_ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeVertical),
+            None => {}
+        }
+
+        let width = |line| if active == Some(line) { 2.5 } else { 1.0 };
+        plot_ui.vline(VLine::new(self.x[0]).color(CURSOR_COLOR).width(width(CursorLine::X1)));
+        plot_ui.vline(VLine::new(self.x[1]).color(CURSOR_COLOR).width(width(CursorLine::X2)).style(LineStyle::dashed_dense()));
+        plot_ui.hline(HLine::new(self.y[0]).color(CURSOR_COLOR).width(width(CursorLine::Y1)));
+        plot_ui.hline(HLine::new(self.y[1]).color(CURSOR_COLOR).width(width(CursorLine::Y2)).style(LineStyle::dashed_dense()));
+    }
+}
+
+// 一个通道在统计范围内的统计结果
+#[derive(Clone, Copy, Debug)]
+pub struct ChannelStats {
+    pub count: usize,
+    pub current: f64,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub rms: f64,
+    pub std_dev: f64,
+    pub peak_to_peak: f64,
+    pub sample_rate: Option<f64>, // 按到达时间估算的采样率(Hz)
+}
+
+impl ChannelStats {
+    pub fn compute<'a>(samples: impl Iterator<Item = &'a Sample>) -> Option<Self> {
+        let mut count = 0usize;
+        let mut first: Option<Sample> = None;
+        let mut last: Option<Sample> = None;
+        let (mut min, mut max) = (f64::INFINITY, f64::NEG_INFINITY);
+        let (mut mean, mut m2, mut sum_sq) = (0.0, 0.0, 0.0);
+
+        for sample in samples {
+            let y = sample.y;
+            count += 1;
+            first.get_or_insert(*sample);
+            last = Some(*sample);
+            min = min.min(y);
+            max = max.max(y);
+            sum_sq += y * y;
+            // Welford 算法，数据量大时方差也不会失去精度
+            let delta = y - mean;
+            mean += delta / count as f64;
+            m2 += delta * (y - mean);
+        }
+
+        let (first, last) = (first?, last?);
+        let duration = last.t - first.t;
+        Some(Self {
+            count,
+            current: last.y,
+            min,
+            max,
+            mean,
+            rms: (sum_sq / count as f64).sqrt(),
+            std_dev: (m2 / count as f64).sqrt(),
+            peak_to_peak: max - min,
+            sample_rate: (count > 1 && duration > 0.0).then(|| (count - 1) as f64 / duration),
+        })
+    }
+}
+
+// 每个显示通道的统计表，可以只统计可见窗口或统计全部历史
+#[derive(Default)]
+pub struct Statistics {
+    pub show: bool,
+    pub full_history: bool,
+    pub results: Vec<(usize, ChannelStats)>,
+    last_update: Option<Instant>,
+}
+
+impl Statistics {
+    // 立即重新统计，例如切换统计范围后
+    pub fn invalidate(&mut self) {
+        self.last_update = None;
+    }
+
+    // visible_x 为当前可见的横轴范围，None 时统计全部历史
+    pub fn update(&mut self, channels: &BTreeMap<usize, PlotChannel>, mode: XAxisMode, visible_x: Option<[f64; 2]>) {
+        if !self.show || self.last_update.is_some_and(|t| t.elapsed() < STATS_INTERVAL) {
+            return;
+        }
+        self.last_update = Some(Instant::now());
+
+        let window = visible_x.filter(|_| !self.full_history);
+        self.results = channels
+            .iter()
+            .filter(|(_, channel)| channel.visible)
+            .filter_map(|(index, channel)| {
+                let stats = match window {
+                    Some([min_x, max_x]) => {
+                        let range = channel.data.index_range(min_x, max_x, mode);
+                        ChannelStats::compute(channel.data.range(range).filter(|s| {
+                            let x = s.x_on(mode);
+                            x >= min_x && x <= max_x
+                        }))
+                    }
+                    None => ChannelStats::compute(channel.data.iter()),
+                };
+                Some((*index, stats?))
+            })
+            .collect();
+    }
+}
+
+pub struct HistogramResult {
+    pub bins: Vec<[f64; 2]>, // (区间中心, 计数)
+    pub bin_width: f64,
+    pub stats: ChannelStats,
+}
+
+// 一个通道数值的分布，用于观察 ADC 噪声和传感器抖动
+pub struct Histogram {
+    pub show: bool,
+    pub channel: usize,
+    pub bins: usize,
+    pub full_history: bool,
+    pub result: Option<HistogramResult>,
+    last_update: Option<Instant>,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            show: false,
+            channel: 0,
+            bins: 50,
+            full_history: false,
+            result: None,
+            last_update: None,
+        }
+    }
+}
+
+impl Histogram {
+    pub fn invalidate(&mut self) {
+        self.last_update = None;
+    }
+
+    // visible_x 为当前可见的横轴范围，None 时统计全部历史
+    pub fn update(&mut self, channels: &BTreeMap<usize, PlotChannel>, mode: XAxisMode, visible_x: Option<[f64; 2]>) {
+        if !self.show || self.last_update.is_some_and(|t| t.elapsed() < STATS_INTERVAL) {
+            return;
+        }
+        self.last_update = Some(Instant::now());
+        self.result = None;
+
+        let Some(channel) = channels.get(&self.channel) else {
+            return;
+        };
+        let samples: Vec<Sample> = match visible_x.filter(|_| !self.full_history) {
+            Some([min_x, max_x]) => channel
+                .data
+                .range(channel.data.index_range(min_x, max_x, mode))
+                .filter(|s| (min_x..=max_x).contains(&s.x_on(mode)))
+                .copied()
+                .collect(),
+            None => channel.data.iter().copied().collect(),
+        };
+        let Some(stats) = ChannelStats::compute(samples.iter()) else {
+            return;
+        };
+
+        // 所有数值相同时使用宽度为 1 的单个区间
+        let bins = if stats.peak_to_peak > 0.0 { self.bins.max(1) } else { 1 };
+        let bin_width = if stats.peak_to_peak > 0.0 { stats.peak_to_peak / bins as f64 } else { 1.0 };
+        let start = if stats.peak_to_peak > 0.0 { stats.min } else { stats.min - 0.5 };
+        let mut counts = vec![0usize; bins];
+        for sample in &samples {
+            let bin = (((sample.y - start) / bin_width) as usize).min(bins - 1);
+            counts[bin] += 1;
+        }
+
+        self.result = Some(HistogramResult {
+            bins: counts
+                .iter()
+                .enumerate()
+                .map(|(i, count)| [start + (i as f64 + 0.5) * bin_width, *count as f64])
+                .collect(),
+            bin_width,
+            stats,
+        });
+    }
+}
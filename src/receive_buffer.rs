@@ -0,0 +1,230 @@
+use std::collections::VecDeque;
+
+// 文本显示时超过这个长度的行强制换行，保证每一行的绘制开销有上限
+const MAX_LINE_BYTES: u64 = 256;
+
+// 可选的接收缓冲区大小
+pub const RECEIVE_LIMITS: [usize; 5] = [256 << 10, 1 << 20, 4 << 20, 16 << 20, 64 << 20];
+
+pub fn limit_label(bytes: usize) -> String {
+    if bytes >= 1 << 20 {
+        format!("{} MB", bytes >> 20)
+    } else {
+        format!("{} KB", bytes >> 10)
+    }
+}
+
+pub fn is_utf8_continuation(byte: u8) -> bool {
+    byte & 0xC0 == 0x80
+}
+
+// 接收数据的环形缓冲区：只保存原始字节，超过上限时丢弃最早的数据
+// 偏移量从清空后收到的第一个字节开始计算，丢弃旧数据后不会改变
+pub struct ReceiveBuffer {
+    bytes: VecDeque<u8>,
+    start: u64,                 // 缓冲区第一个字节的偏移量
+    line_starts: VecDeque<u64>, // 文本显示时每一行起点的偏移量
+    pub max_bytes: usize,
+}
+
+impl ReceiveBuffer {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            bytes: VecDeque::new(),
+            start: 0,
+            line_starts: VecDeque::from([0]),
+            max_bytes,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn start_offset(&self) -> u64 {
+        self.start
+    }
+
+    pub fn end_offset(&self) -> u64 {
+        self.start + self.bytes.len() as u64
+    }
+
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+        self.start = 0;
+        self.line_starts = VecDeque::from([0]);
+    }
+
+    pub fn push(&mut self, data: &[u8]) {
+        let mut offset = self.end_offset();
+        let mut line_start = self.line_starts.back().copied().unwrap_or(offset);
+        for &byte in data {
+            // 强制换行时不拆开 UTF-8 多字节字符，最多多等 3 个后续字节
+            let length = offset - line_start;
+            if length >= MAX_LINE_BYTES + 3 || (length >= MAX_LINE_BYTES && !is_utf8_continuation(byte)) {
+                line_start = offset;
+                self.line_starts.push_back(line_start);
+            }
+            offset += 1;
+            if byte == b'\n' {
+                line_start = offset;
+                self.line_starts.push_back(line_start);
+            }
+        }
+        self.bytes.extend(data);
+        self.trim();
+    }
+
+    pub fn set_max_bytes(&mut self, max_bytes: usize) {
+        self.max_bytes = max_bytes;
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        let excess = self.bytes.len().saturating_sub(self.max_bytes);
+        if excess == 0 {
+            return;
+        }
+        self.bytes.drain(..excess);
+        self.start += excess as u64;
+        // 第一行的开头可能已被丢弃，从缓冲区起点开始显示
+        while self.line_starts.len() > 1 && self.line_starts[1] <= self.start {
+            self.line_starts.pop_front();
+        }
+        if let Some(first) = self.line_starts.front_mut() {
+            *first = (*first).max(self.start);
+        }
+    }
+
+    // 按偏移量取数据，超出缓冲区的部分被忽略
+    pub fn range(&self, start: u64, end: u64) -> impl Iterator<Item = u8> + '_ {
+        let from = start.clamp(self.start, self.end_offset()) - self.start;
+        let to = end.clamp(self.start, self.end_offset()) - self.start;
+        self.bytes.range(from as usize..to.max(from) as usize).copied()
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.bytes.iter().copied().collect()
+    }
+
+    pub fn line_count(&self) -> usize {
+        // 最后一行为空时不显示
+        match self.line_starts.back() {
+            Some(&last) if last == self.end_offset() => self.line_starts.len() - 1,
+            _ => self.line_starts.len(),
+        }
+    }
+
+    // 第 index 行的偏移量范围，包含行尾的换行符
+    pub fn line_range(&self, index: usize) -> (u64, u64) {
+        let start = self.line_starts.get(index).copied().unwrap_or(self.end_offset());
+        let end = self.line_starts.get(index + 1).copied().unwrap_or(self.end_offset());
+        (start, end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(buffer: &ReceiveBuffer) -> Vec<String> {
+        (0..buffer.line_count())
+            .map(|i| {
+                let (start, end) = buffer.line_range(i);
+                String::from_utf8(buffer.range(start, end).collect()).unwrap()
+            })
+            .collect()
+    }
+
+    // 各行首尾相接，正好覆盖整个缓冲区
+    fn check_lines(buffer: &ReceiveBuffer) {
+        let mut offset = buffer.start_offset();
+        for i in 0..buffer.line_count() {
+            let (start, end) = buffer.line_range(i);
+            assert_eq!(start, offset);
+            assert!(end > start);
+            offset = end;
+        }
+        assert_eq!(offset, buffer.end_offset());
+    }
+
+    #[test]
+    fn splits_lines() {
+        let mut buffer = ReceiveBuffer::new(1024);
+        assert_eq!(buffer.line_count(), 0);
+        buffer.push(b"ab\ncd");
+        buffer.push(b"\nef");
+        assert_eq!(lines(&buffer), ["ab\n", "cd\n", "ef"]);
+        buffer.push(b"\n");
+        assert_eq!(lines(&buffer), ["ab\n", "cd\n", "ef\n"]);
+        check_lines(&buffer);
+
+        buffer.clear();
+        assert_eq!((buffer.len(), buffer.line_count(), buffer.start_offset()), (0, 0, 0));
+    }
+
+    #[test]
+    fn trims_in_middle_of_line() {
+        let mut buffer = ReceiveBuffer::new(8);
+        buffer.push(b"hello\nworld\n");
+        assert_eq!(buffer.len(), 8);
+        assert_eq!(buffer.start_offset(), 4);
+        assert_eq!(lines(&buffer), ["o\n", "world\n"]);
+        check_lines(&buffer);
+
+        // 在行首处丢弃时不留下空行
+        buffer.push(b"ab");
+        assert_eq!(buffer.start_offset(), 6);
+        assert_eq!(lines(&buffer), ["world\n", "ab"]);
+        check_lines(&buffer);
+    }
+
+    #[test]
+    fn shrinking_limit_trims_lines() {
+        let mut buffer = ReceiveBuffer::new(1 << 20);
+        for i in 0..1000 {
+            buffer.push(format!("line {}\n", i).as_bytes());
+        }
+        let end = buffer.end_offset();
+        buffer.set_max_bytes(20);
+        assert_eq!(buffer.len(), 20);
+        assert_eq!(buffer.end_offset(), end);
+        assert_eq!(lines(&buffer), ["7\n", "line 998\n", "line 999\n"]);
+        check_lines(&buffer);
+
+        // 放大上限不影响已有数据
+        buffer.set_max_bytes(1 << 20);
+        assert_eq!(buffer.len(), 20);
+
+        buffer.set_max_bytes(0);
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.line_count(), 0);
+        buffer.set_max_bytes(16);
+        buffer.push(b"x\ny");
+        assert_eq!(lines(&buffer), ["x\n", "y"]);
+        check_lines(&buffer);
+    }
+
+    #[test]
+    fn breaks_long_lines_between_characters() {
+        let mut buffer = ReceiveBuffer::new(1 << 20);
+        let text = "中".repeat(300);
+        buffer.push(text.as_bytes());
+        check_lines(&buffer);
+        let lines = lines(&buffer);
+        assert_eq!(lines.concat(), text);
+        assert!(lines.len() > 1);
+        for line in &lines[..lines.len() - 1] {
+            assert!((MAX_LINE_BYTES as usize..=MAX_LINE_BYTES as usize + 3).contains(&line.len()));
+        }
+
+        // 丢弃的数据从多字节字符中间开始时，第一行从缓冲区起点开始
+        buffer.set_max_bytes(100);
+        check_lines(&buffer);
+        assert_eq!(buffer.line_count(), 1);
+    }
+}
@@ -0,0 +1,286 @@
+use crate::app::SerialAssistant;
+use crate::receive_buffer::{is_utf8_continuation, ReceiveBuffer};
+use crate::utils;
+use eframe::egui;
+
+// HEX 显示时每行的字节数
+const HEX_ROW_BYTES: u64 = 16;
+// 每行的列位置(字符数)：8 位偏移量，两组 8 字节的十六进制，ASCII 列
+const HEX_COLUMN: usize = 10;
+const ASCII_COLUMN: usize = HEX_COLUMN + 3 * HEX_ROW_BYTES as usize + 2;
+
+fn hex_column(i: usize) -> usize {
+    HEX_COLUMN + 3 * i + i / 8
+}
+
+// 接收区域的显示方式，都由缓冲区中的原始字节生成，随时切换不会改变数据
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ReceiveDisplay {
+    Text,
+    Hex,
+    Mixed, // 文本中的控制字符和无效字节显示为 <XX>
+}
+
+impl ReceiveDisplay {
+    pub const ALL: [ReceiveDisplay; 3] = [ReceiveDisplay::Text, ReceiveDisplay::Hex, ReceiveDisplay::Mixed];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReceiveDisplay::Text => "文本",
+            ReceiveDisplay::Hex => "HEX",
+            ReceiveDisplay::Mixed => "混合",
+        }
+    }
+}
+
+// HEX 视图中选中的字节，偏移量与接收缓冲区相同
+#[derive(Default)]
+pub struct HexSelection {
+    pub range: Option<(u64, u64)>, // 起点和终点(都包含)，起点为按下鼠标的位置
+    dragging: bool,
+}
+
+impl HexSelection {
+    // 按从小到大排列的范围 [start, end)
+    pub fn bounds(&self) -> Option<(u64, u64)> {
+        self.range.map(|(a, b)| (a.min(b), a.max(b) + 1))
+    }
+
+    fn contains(&self, offset: u64) -> bool {
+        self.bounds().is_some_and(|(start, end)| (start..end).contains(&offset))
+    }
+}
+
+// 接收区域：只绘制可见的行，缓冲区很大时也不会卡顿
+pub fn render_receive_view(app: &mut SerialAssistant, ui: &mut egui::Ui, max_height: f32) {
+    if app.receive_display == ReceiveDisplay::Hex {
+        render_hex_dump(app, ui, max_height);
+        return;
+    }
+
+    let font = egui::TextStyle::Monospace.resolve(ui.style());
+    let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+    let text_color = ui.visuals().text_color();
+    let byte_color = ui.visuals().weak_text_color();
+    let buffer = &app.received;
+    let mixed = app.receive_display == ReceiveDisplay::Mixed;
+    egui::ScrollArea::both()
+        .id_salt("receive_area_scroll")
+        .max_height(max_height)
+        .auto_shrink([false, false])
+        .stick_to_bottom(app.auto_scroll)
+        .show_rows(ui, row_height, buffer.line_count(), |ui, visible| {
+            for row in visible {
+                let (start, end) = buffer.line_range(row);
+                let line: Vec<u8> = buffer.range(start, end).collect();
+                if mixed {
+                    let mut job = egui::text::LayoutJob::default();
+                    for (text, is_byte) in mixed_segments(&line) {
+                        let color = if is_byte { byte_color } else { text_color };
+                        job.append(&text, 0.0, egui::TextFormat::simple(font.clone(), color));
+                    }
+                    ui.add(egui::Label::new(job).extend());
+                } else {
+                    // 旧数据被丢弃时第一行可能从多字节字符的中间开始
+                    let skip = if start == buffer.start_offset() { line.iter().take(3).take_while(|b| is_utf8_continuation(**b)).count() } else { 0 };
+                    let text = String::from_utf8_lossy(&line[skip..]).trim_end_matches(['\n', '\r']).to_string();
+                    ui.add(egui::Label::new(egui::RichText::new(text).monospace()).extend());
+                }
+            }
+        });
+}
+
+// 把字节分成可显示的文本和 <XX> 形式的字节，返回 (内容, 是否为字节)
+fn mixed_segments(bytes: &[u8]) -> Vec<(String, bool)> {
+    let mut segments: Vec<(String, bool)> = Vec::new();
+    let mut push = |text: &str, is_byte: bool| match segments.last_mut() {
+        Some((last, kind)) if *kind == is_byte => last.push_str(text),
+        _ => segments.push((text.to_string(), is_byte)),
+    };
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            let mut utf8 = [0; 4];
+            let encoded = c.encode_utf8(&mut utf8);
+            if c.is_control() {
+                for byte in encoded.bytes() {
+                    push(&format!("<{:02X}>", byte), true);
+                }
+            } else {
+                push(encoded, false);
+            }
+        }
+        for byte in chunk.invalid() {
+            push(&format!("<{:02X}>", byte), true);
+        }
+    }
+    segments
+}
+
+// 经典十六进制转储：偏移量、16 个字节的十六进制和 ASCII 列，拖动选择字节，两列同时高亮
+fn render_hex_dump(app: &mut SerialAssistant, ui: &mut egui::Ui, max_height: f32) {
+    let font = egui::TextStyle::Monospace.resolve(ui.style());
+    let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+    let char_width = ui.fonts(|fonts| fonts.glyph_width(&font, '0'));
+    let row_width = char_width * (ASCII_COLUMN + HEX_ROW_BYTES as usize) as f32;
+
+    let buffer = &app.received;
+    let selection = &mut app.hex_selection;
+    // 行按偏移量对齐到 16 字节
+    let base = buffer.start_offset() - buffer.start_offset() % HEX_ROW_BYTES;
+    let rows = (buffer.end_offset() - base).div_ceil(HEX_ROW_BYTES) as usize;
+    let inspector_height = if selection.range.is_some() { 140.0 } else { 0.0 };
+
+    let pointer = ui.input(|i| i.pointer.clone());
+    let mut hovered = None;
+    egui::ScrollArea::both()
+        .id_salt("receive_area_scroll")
+        .max_height(max_height - inspector_height)
+        .auto_shrink([false, false])
+        .stick_to_bottom(app.auto_scroll)
+        .drag_to_scroll(false)
+        .show_rows(ui, row_height, rows, |ui, visible| {
+            for row in visible {
+                let offset = base + row as u64 * HEX_ROW_BYTES;
+                let bytes: Vec<Option<u8>> = (offset..offset + HEX_ROW_BYTES)
+                    .map(|o| buffer.range(o, o + 1).next())
+                    .collect();
+                let (rect, _) = ui.allocate_exact_size(egui::vec2(row_width, row_height), egui::Sense::click_and_drag());
+                let x_of = |column: usize| rect.left() + column as f32 * char_width;
+
+                // 先画选中字节的背景
+                let highlight = ui.visuals().selection.bg_fill;
+                for (i, byte) in bytes.iter().enumerate() {
+                    if byte.is_some() && selection.contains(offset + i as u64) {
+                        let hex = egui::Rect::from_min_size(egui::pos2(x_of(hex_column(i)), rect.top()), egui::vec2(char_width * 2.0, row_height));
+                        let ascii = egui::Rect::from_min_size(egui::pos2(x_of(ASCII_COLUMN + i), rect.top()), egui::vec2(char_width, row_height));
+                        ui.painter().rect_filled(hex, 0.0, highlight);
+                        ui.painter().rect_filled(ascii, 0.0, highlight);
+                    }
+                }
+                ui.painter().text(rect.left_top(), egui::Align2::LEFT_TOP, hex_row(offset, &bytes), font.clone(), ui.visuals().text_color());
+
+                if let Some(pos) = pointer.hover_pos().filter(|pos| rect.contains(*pos)) {
+                    let column = ((pos.x - rect.left()) / char_width) as usize;
+                    let index = if column >= ASCII_COLUMN {
+                        Some(column - ASCII_COLUMN)
+                    } else {
+                        (0..HEX_ROW_BYTES as usize).find(|&i| (hex_column(i)..hex_column(i) + 3).contains(&column))
+                    };
+                    hovered = index.filter(|&i| i < bytes.len() && bytes[i].is_some()).map(|i| offset + i as u64);
+                }
+            }
+        });
+
+    // 按下开始选择，按住 Shift 时扩展选择，拖动时更新终点
+    let shift = ui.input(|i| i.modifiers.shift);
+    if pointer.primary_pressed() {
+        selection.dragging = hovered.is_some();
+        if let Some(offset) = hovered {
+            selection.range = match (shift, selection.range) {
+                (true, Some((anchor, _))) => Some((anchor, offset)),
+                _ => Some((offset, offset)),
+            };
+        }
+    }
+    if selection.dragging
+        && pointer.primary_down()
+        && let (Some(offset), Some((anchor, _))) = (hovered, selection.range)
+    {
+        selection.range = Some((anchor, offset));
+    }
+    if !pointer.primary_down() {
+        selection.dragging = false;
+    }
+    // 选中的数据已被丢弃时取消选择
+    if selection.bounds().is_some_and(|(start, _)| start < buffer.start_offset()) {
+        selection.range = None;
+    }
+
+    if let Some((start, end)) = selection.bounds() {
+        let selected: Vec<u8> = buffer.range(start, end).collect();
+        ui.separator();
+        render_selection(ui, start, &selected);
+    }
+}
+
+fn hex_row(offset: u64, bytes: &[Option<u8>]) -> String {
+    let mut line = format!("{:08X}  ", offset);
+    for (i, byte) in bytes.iter().enumerate() {
+        if i == 8 {
+            line.push(' ');
+        }
+        match byte {
+            Some(byte) => line.push_str(&format!("{:02X} ", byte)),
+            None => line.push_str("   "),
+        }
+    }
+    line.push(' ');
+    for byte in bytes {
+        line.push(match byte {
+            Some(b) if b.is_ascii_graphic() || *b == b' ' => *b as char,
+            Some(_) => '.',
+            None => ' ',
+        });
+    }
+    line
+}
+
+// 选中字节的复制按钮和数值解析
+fn render_selection(ui: &mut egui::Ui, start: u64, selected: &[u8]) {
+    ui.horizontal_wrapped(|ui| {
+        ui.label(format!("选中 {} 字节 @ {:08X}", selected.len(), start));
+        // 没有输入框获得焦点时 Ctrl+C 复制选中的字节
+        let copy_key = ui.memory(|m| m.focused().is_none()) && ui.input(|i| i.events.iter().any(|e| matches!(e, egui::Event::Copy)));
+        if ui.small_button("复制HEX").clicked() || copy_key {
+            ui.ctx().copy_text(utils::bytes_to_hex(selected).trim_end().to_string());
+        }
+        if ui.small_button("复制C数组").clicked() {
+            ui.ctx().copy_text(utils::bytes_to_c_array(selected));
+        }
+        if ui.small_button("复制Base64").clicked() {
+            ui.ctx().copy_text(utils::bytes_to_base64(selected));
+        }
+    });
+
+    // 从选中的第一个字节开始按不同类型解析
+    fn read<const N: usize>(bytes: &[u8]) -> Option<[u8; N]> {
+        bytes.get(..N)?.try_into().ok()
+    }
+    let rows: [(&str, Option<(String, String)>); 6] = [
+        ("u16", read::<2>(selected).map(|b| (u16::from_le_bytes(b).to_string(), u16::from_be_bytes(b).to_string()))),
+        ("i16", read::<2>(selected).map(|b| (i16::from_le_bytes(b).to_string(), i16::from_be_bytes(b).to_string()))),
+        ("u32", read::<4>(selected).map(|b| (u32::from_le_bytes(b).to_string(), u32::from_be_bytes(b).to_string()))),
+        ("i32", read::<4>(selected).map(|b| (i32::from_le_bytes(b).to_string(), i32::from_be_bytes(b).to_string()))),
+        ("f32", read::<4>(selected).map(|b| (f32::from_le_bytes(b).to_string(), f32::from_be_bytes(b).to_string()))),
+        ("f64", read::<8>(selected).map(|b| (f64::from_le_bytes(b).to_string(), f64::from_be_bytes(b).to_string()))),
+    ];
+    egui::Grid::new("hex_inspector").striped(true).show(ui, |ui| {
+        ui.strong("类型");
+        ui.strong("小端");
+        ui.strong("大端");
+        ui.end_row();
+        for (name, values) in rows {
+            let Some((little, big)) = values else {
+                continue;
+            };
+            ui.monospace(name);
+            ui.monospace(little);
+            ui.monospace(big);
+            ui.end_row();
+        }
+    });
+}
+
+// 供复制全部数据使用，格式与当前的显示方式相同
+pub fn buffer_text(buffer: &ReceiveBuffer, display: ReceiveDisplay) -> String {
+    let data = buffer.to_vec();
+    match display {
+        ReceiveDisplay::Text => String::from_utf8_lossy(&data).into_owned(),
+        ReceiveDisplay::Hex => utils::bytes_to_hex(&data),
+        // 换行符后保留换行，和显示的一样按行排列
+        ReceiveDisplay::Mixed => mixed_segments(&data)
+            .into_iter()
+            .map(|(text, is_byte)| if is_byte { text.replace("<0A>", "<0A>\n") } else { text })
+            .collect(),
+    }
+}
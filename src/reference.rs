@@ -0,0 +1,165 @@
+use crate::channel::PlotChannel;
+use crate::import::CsvImport;
+use crate::sample_buffer::{Sample, XAxisMode};
+use chrono::{DateTime, Local};
+use egui::Color32;
+use std::collections::BTreeMap;
+
+// 参考波形中一个通道的数据，横坐标和时间相对于快照的第一个点
+pub struct ReferenceTrace {
+    pub channel: usize,
+    pub label: String,
+    pub color: Color32,
+    pub samples: Vec<Sample>,
+}
+
+impl ReferenceTrace {
+    // 按相对横坐标线性插值，超出范围时返回 None
+    pub fn value_at(&self, x: f64, mode: XAxisMode) -> Option<f64> {
+        let index = self.samples.partition_point(|s| s.x_on(mode) < x);
+        let after = self.samples.get(index)?;
+        if index == 0 {
+            return (after.x_on(mode) == x).then_some(after.y);
+        }
+        let before = self.samples[index - 1];
+        let span = after.x_on(mode) - before.x_on(mode);
+        if span <= 0.0 {
+            return Some(after.y);
+        }
+        Some(before.y + (after.y - before.y) * (x - before.x_on(mode)) / span)
+    }
+}
+
+// 一组参考波形，例如同一次快照的所有通道
+pub struct Reference {
+    pub name: String,
+    pub visible: bool,
+    pub show_difference: bool, // 显示实时通道减去参考波形的差值
+    pub x_offset: f64,         // 按采样序号显示时参考波形起点的位置
+    pub t_offset: f64,         // 按时间显示时参考波形起点的时间(秒)
+    pub traces: Vec<ReferenceTrace>,
+}
+
+impl Reference {
+    pub fn offset(&self, mode: XAxisMode) -> f64 {
+        if mode == XAxisMode::Index { self.x_offset } else { self.t_offset }
+    }
+
+    pub fn offset_mut(&mut self, mode: XAxisMode) -> &mut f64 {
+        if mode == XAxisMode::Index { &mut self.x_offset } else { &mut self.t_offset }
+    }
+
+    // 快照显示的模拟通道，window 为 None 时取全部历史，参考波形放在原来的位置
+    pub fn snapshot(name: String, channels: &BTreeMap<usize, PlotChannel>, window: Option<[f64; 2]>, mode: XAxisMode) -> Option<Self> {
+        let mut origin: Option<Sample> = None;
+        let mut traces = Vec::new();
+        for (index, channel) in channels.iter().filter(|(_, c)| c.visible && !c.is_logic()) {
+            let samples: Vec<Sample> = match window {
+                Some([min_x, max_x]) => channel
+                    .data
+                    .range(channel.data.index_range(min_x, max_x, mode))
+                    .filter(|s| (min_x..=max_x).contains(&s.x_on(mode)))
+                    .copied()
+                    .collect(),
+                None => channel.data.iter().copied().collect(),
+            };
+            let Some(first) = samples.first() else {
+                continue;
+            };
+            origin = Some(match origin {
+                Some(o) => Sample { x: o.x.min(first.x), y: 0.0, t: o.t.min(first.t) },
+                None => *first,
+            });
+            traces.push(ReferenceTrace {
+                channel: *index,
+                label: channel.label(),
+                color: channel.color,
+                samples,
+            });
+        }
+
+        let origin = origin?;
+        for trace in &mut traces {
+            for sample in &mut trace.samples {
+                sample.x -= origin.x;
+                sample.t -= origin.t;
+            }
+        }
+        Some(Self {
+            name,
+            visible: true,
+            show_difference: false,
+            x_offset: origin.x,
+            t_offset: origin.t,
+            traces,
+        })
+    }
+
+    // 由导入的 CSV 生成参考波形，起点放在 0
+    pub fn from_csv(name: String, csv: &CsvImport) -> Option<Self> {
+        let first_time = csv.rows.iter().find_map(|row| row.time);
+        let mut traces: BTreeMap<usize, ReferenceTrace> = BTreeMap::new();
+        for (row_index, row) in csv.rows.iter().enumerate() {
+            let t = row
+                .t
+                .or_else(|| Some((row.time? - first_time?).num_microseconds()? as f64 / 1e6))
+                .unwrap_or(row_index as f64);
+            for value in &row.values {
+                let channel = value.channel;
+                let trace = traces.entry(channel).or_insert_with(|| ReferenceTrace {
+                    channel,
+                    label: csv.channels.iter().find(|(i, _)| *i == channel).map_or_else(|| format!("通道 {}", channel), |(_, n)| n.clone()),
+                    color: crate::channel::default_color(channel),
+                    samples: Vec::new(),
+                });
+                // 没有横坐标列时按采样序号
+                let x = value.x.unwrap_or(trace.samples.len() as f64);
+                trace.samples.push(Sample { x, y: value.y, t });
+            }
+        }
+
+        let first = |f: fn(&Sample) -> f64| traces.values().filter_map(|trace| trace.samples.first()).map(f).reduce(f64::min);
+        let (x0, t0) = (first(|s| s.x)?, first(|s| s.t)?);
+        for sample in traces.values_mut().flat_map(|trace| trace.samples.iter_mut()) {
+            sample.x -= x0;
+            sample.t -= t0;
+        }
+        Some(Self {
+            name,
+            visible: true,
+            show_difference: false,
+            x_offset: 0.0,
+            t_offset: 0.0,
+            traces: traces.into_values().collect(),
+        })
+    }
+
+    // 保存为与导出数据相同格式的 CSV，可以再作为参考波形或数据导入
+    pub fn save_csv(&self, path: &std::path::Path, epoch: DateTime<Local>) -> std::io::Result<usize> {
+        let columns = self
+            .traces
+            .iter()
+            .map(|trace| {
+                let samples: Box<dyn Iterator<Item = &Sample>> = Box::new(trace.samples.iter());
+                (trace.channel, trace.label.clone(), samples)
+            })
+            .collect();
+        crate::export::write_csv(path, columns, epoch)
+    }
+}
+
+pub struct References {
+    pub show: bool,
+    pub name: String, // 下一次快照的名称
+    pub references: Vec<Reference>,
+}
+
+impl Default for References {
+    fn default() -> Self {
+        Self {
+            show: false,
+            name: "参考 1".to_string(),
+            references: Vec::new(),
+        }
+    }
+}
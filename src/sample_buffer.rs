@@ -0,0 +1,428 @@
+use std::collections::VecDeque;
+
+// 历史长度模式下每个通道最多保留的点数，防止内存无限增长
+pub const MAX_HISTORY_POINTS: usize = 10_000_000;
+
+// 一个波形数据点，t 为数据到达的时间(相对采集开始的秒数)
+#[derive(Clone, Copy, Debug)]
+pub struct Sample {
+    pub x: f64,
+    pub y: f64,
+    pub t: f64,
+}
+
+// 横轴模式：采样序号(或脚本提供的 X)、相对采集开始的秒数、绝对时间
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum XAxisMode {
+    Index,
+    Relative,
+    Absolute,
+}
+
+impl XAxisMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            XAxisMode::Index => "采样序号",
+            XAxisMode::Relative => "相对时间(s)",
+            XAxisMode::Absolute => "绝对时间",
+        }
+    }
+}
+
+impl Sample {
+    // 数据点在指定横轴模式下的横坐标，时间模式下使用到达时间
+    pub fn x_on(&self, mode: XAxisMode) -> f64 {
+        match mode {
+            XAxisMode::Index => self.x,
+            XAxisMode::Relative | XAxisMode::Absolute => self.t,
+        }
+    }
+}
+
+// 历史长度：按点数或按时间保留
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HistoryLimit {
+    Points(usize),
+    Seconds(f64),
+}
+
+impl HistoryLimit {
+    pub const PRESETS: [HistoryLimit; 9] = [
+        HistoryLimit::Points(1_000),
+        HistoryLimit::Points(10_000),
+        HistoryLimit::Points(100_000),
+        HistoryLimit::Points(1_000_000),
+        HistoryLimit::Points(MAX_HISTORY_POINTS),
+        HistoryLimit::Seconds(10.0),
+        HistoryLimit::Seconds(60.0),
+        HistoryLimit::Seconds(600.0),
+        HistoryLimit::Seconds(3600.0),
+    ];
+
+    pub fn label(&self) -> String {
+        match self {
+            HistoryLimit::Points(n) if *n >= 1_000_000 => format!("{} M 点", n / 1_000_000),
+            HistoryLimit::Points(n) if *n >= 1_000 => format!("{} k 点", n / 1_000),
+            HistoryLimit::Points(n) => format!("{} 点", n),
+            HistoryLimit::Seconds(s) => format!("{} 秒", s),
+        }
+    }
+}
+
+// 最大/最小值缓存的段数上限，超过时每段的点数加倍
+const MIN_MAX_MAX_BUCKETS: usize = 8192;
+// 每段最初的点数
+const MIN_MAX_BUCKET_SIZE: usize = 16;
+
+// 按固定点数分段记录每段的最小和最大值，随数据增加增量更新，
+// 抽取全部历史时只需合并各段的结果，不用每帧遍历所有数据
+struct MinMaxCache {
+    bucket_size: usize,
+    first_bucket: usize, // 第一段的编号，按从清空开始的绝对下标计算
+    buckets: VecDeque<(Sample, Sample)>,
+}
+
+impl Default for MinMaxCache {
+    fn default() -> Self {
+        Self {
+            bucket_size: MIN_MAX_BUCKET_SIZE,
+            first_bucket: 0,
+            buckets: VecDeque::new(),
+        }
+    }
+}
+
+impl MinMaxCache {
+    fn push(&mut self, absolute: usize, sample: Sample) {
+        let bucket = absolute / self.bucket_size;
+        let end = self.first_bucket + self.buckets.len();
+        match self.buckets.back_mut() {
+            Some((min, max)) if bucket < end => {
+                if sample.y < min.y {
+                    *min = sample;
+                }
+                if sample.y > max.y {
+                    *max = sample;
+                }
+            }
+            Some(_) => self.buckets.push_back((sample, sample)),
+            None => {
+                self.first_bucket = bucket;
+                self.buckets.push_back((sample, sample));
+            }
+        }
+        if self.buckets.len() > MIN_MAX_MAX_BUCKETS {
+            self.coarsen();
+        }
+    }
+
+    // 相邻两段合并为一段
+    fn coarsen(&mut self) {
+        let mut merged: VecDeque<(Sample, Sample)> = VecDeque::with_capacity(self.buckets.len() / 2 + 1);
+        for (i, bucket) in self.buckets.drain(..).enumerate() {
+            let id = (self.first_bucket + i) / 2;
+            let end = self.first_bucket / 2 + merged.len();
+            match merged.back_mut() {
+                Some(last) if id < end => *last = merge(*last, bucket),
+                _ => merged.push_back(bucket),
+            }
+        }
+        self.buckets = merged;
+        self.first_bucket /= 2;
+        self.bucket_size *= 2;
+    }
+}
+
+fn merge(a: (Sample, Sample), b: (Sample, Sample)) -> (Sample, Sample) {
+    (if b.0.y < a.0.y { b.0 } else { a.0 }, if b.1.y > a.1.y { b.1 } else { a.1 })
+}
+
+// 按原始顺序输出一段的最小值和最大值，避免折线来回跳动
+fn push_min_max(points: &mut Vec<[f64; 2]>, (min, max): (Sample, Sample), mode: XAxisMode) {
+    let (min_x, max_x) = (min.x_on(mode), max.x_on(mode));
+    if min_x <= max_x {
+        points.push([min_x, min.y]);
+        points.push([max_x, max.y]);
+    } else {
+        points.push([max_x, max.y]);
+        points.push([min_x, min.y]);
+    }
+}
+
+// 环形缓冲区，超出历史长度时从头部丢弃旧数据
+#[derive(Default)]
+pub struct SampleBuffer {
+    samples: VecDeque<Sample>,
+    dropped: usize, // 清空后已丢弃的点数，即第一个点的绝对下标
+    min_max: MinMaxCache,
+}
+
+impl SampleBuffer {
+    pub fn push(&mut self, sample: Sample, limit: HistoryLimit) {
+        self.min_max.push(self.dropped + self.samples.len(), sample);
+        self.samples.push_back(sample);
+        self.trim(limit);
+    }
+
+    // 按历史长度丢弃旧数据
+    pub fn trim(&mut self, limit: HistoryLimit) {
+        let max_points = match limit {
+            HistoryLimit::Points(n) => n.clamp(1, MAX_HISTORY_POINTS),
+            HistoryLimit::Seconds(seconds) => {
+                if let Some(newest) = self.samples.back().map(|s| s.t) {
+                    let start = self.samples.partition_point(|s| s.t < newest - seconds);
+                    self.drop_front(start);
+                }
+                MAX_HISTORY_POINTS
+            }
+        };
+        if self.samples.len() > max_points {
+            self.drop_front(self.samples.len() - max_points);
+        }
+    }
+
+    fn drop_front(&mut self, count: usize) {
+        if count == 0 {
+            return;
+        }
+        self.samples.drain(..count);
+        self.dropped += count;
+
+        let cache = &mut self.min_max;
+        let first = self.dropped / cache.bucket_size;
+        while cache.first_bucket < first && cache.buckets.pop_front().is_some() {
+            cache.first_bucket += 1;
+        }
+        // 第一段只剩一部分时重新计算
+        let remaining = (cache.bucket_size - self.dropped % cache.bucket_size).min(self.samples.len());
+        if let Some(front) = cache.buckets.front_mut()
+            && let Some(first) = self.samples.front()
+        {
+            *front = self.samples.range(1..remaining).fold((*first, *first), |bucket, s| merge(bucket, (*s, *s)));
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.dropped = 0;
+        self.min_max = MinMaxCache::default();
+    }
+
+    pub fn last(&self) -> Option<&Sample> {
+        self.samples.back()
+    }
+
+    pub fn first(&self) -> Option<&Sample> {
+        self.samples.front()
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Sample> + ExactSizeIterator {
+        self.samples.iter()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Sample> {
+        self.samples.get(index)
+    }
+
+    pub fn range(&self, range: std::ops::Range<usize>) -> impl DoubleEndedIterator<Item = &Sample> + ExactSizeIterator {
+        self.samples.range(range)
+    }
+
+    // 第一个横坐标大于 x 的数据下标
+    pub fn first_after(&self, x: f64, mode: XAxisMode) -> usize {
+        self.samples.partition_point(|s| s.x_on(mode) <= x)
+    }
+
+    // 横坐标在 [x_min, x_max] 内的数据下标范围，两侧各多包含一个点，使曲线延伸到边界
+    // 要求横坐标单调递增，由 PlotChannel::next_sample_x 保证
+    pub fn index_range(&self, x_min: f64, x_max: f64, mode: XAxisMode) -> std::ops::Range<usize> {
+        let start = self.samples.partition_point(|s| s.x_on(mode) < x_min).saturating_sub(1);
+        let end = (self.samples.partition_point(|s| s.x_on(mode) <= x_max) + 1).min(self.samples.len());
+        start..end.max(start)
+    }
+
+    // 最大/最小值抽取：把范围内的数据按下标分成 buckets 段，每段只保留最小和最大值两个点，
+    // 保证绘制的点数与屏幕宽度相当，同时不丢失尖峰
+    pub fn decimate(&self, range: std::ops::Range<usize>, buckets: usize, mode: XAxisMode) -> Vec<[f64; 2]> {
+        let count = range.len();
+        if count <= buckets.max(1) * 2 {
+            return self.samples.range(range).map(|s| [s.x_on(mode), s.y]).collect();
+        }
+
+        let buckets = buckets.max(1);
+        // 每段覆盖多个缓存段时使用缓存，只有两端不完整的部分需要遍历
+        if count / buckets / self.min_max.bucket_size >= 2 {
+            return self.decimate_cached(range, buckets, mode);
+        }
+
+        let mut points = Vec::with_capacity(buckets * 2);
+        for bucket in 0..buckets {
+            let start = range.start + count * bucket / buckets;
+            let end = range.start + count * (bucket + 1) / buckets;
+            if let Some(min_max) = self.scan_min_max(start..end) {
+                push_min_max(&mut points, min_max, mode);
+            }
+        }
+        points
+    }
+
+    fn scan_min_max(&self, range: std::ops::Range<usize>) -> Option<(Sample, Sample)> {
+        let first = *self.samples.get(range.start).filter(|_| range.start < range.end)?;
+        Some(self.samples.range(range.start + 1..range.end).fold((first, first), |bucket, s| merge(bucket, (*s, *s))))
+    }
+
+    // 相邻的缓存段合并为不超过 buckets 个输出段
+    fn decimate_cached(&self, range: std::ops::Range<usize>, buckets: usize, mode: XAxisMode) -> Vec<[f64; 2]> {
+        let cache = &self.min_max;
+        let size = cache.bucket_size;
+        // 范围内完整的缓存段 [first, last)
+        let first = (self.dropped + range.start).div_ceil(size);
+        let last = ((self.dropped + range.end) / size).max(first);
+        let start_of = |bucket: usize| (bucket * size - self.dropped).clamp(range.start, range.end);
+        let group = (last - first).div_ceil(buckets).max(1);
+
+        let mut points = Vec::with_capacity(buckets * 2 + 4);
+        if let Some(head) = self.scan_min_max(range.start..start_of(first)) {
+            push_min_max(&mut points, head, mode);
+        }
+        let mut bucket = first;
+        while bucket < last {
+            let end = (bucket + group).min(last);
+            let merged = cache
+                .buckets
+                .range(bucket - cache.first_bucket..end - cache.first_bucket)
+                .copied()
+                .reduce(merge);
+            if let Some(merged) = merged {
+                push_min_max(&mut points, merged, mode);
+            }
+            bucket = end;
+        }
+        if let Some(tail) = self.scan_min_max(start_of(last)..range.end) {
+            push_min_max(&mut points, tail, mode);
+        }
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 带尖峰的测试波形，尖峰位置由绝对下标决定
+    fn sample(i: usize) -> Sample {
+        let y = match i % 9973 {
+            17 => 1000.0 + i as f64,
+            4242 => -1000.0 - i as f64,
+            _ => (i as f64 * 0.01).sin(),
+        };
+        Sample { x: i as f64, y, t: i as f64 * 0.001 }
+    }
+
+    fn filled(count: usize, limit: HistoryLimit) -> SampleBuffer {
+        let mut buffer = SampleBuffer::default();
+        for i in 0..count {
+            buffer.push(sample(i), limit);
+        }
+        buffer
+    }
+
+    // 抽取结果的最大/最小值与遍历范围内所有数据的结果相同，输出的点都来自原始数据且按 X 排列
+    fn check_decimate(buffer: &SampleBuffer, range: std::ops::Range<usize>, buckets: usize) {
+        let points = buffer.decimate(range.clone(), buckets, XAxisMode::Index);
+        let expected: Vec<[f64; 2]> = buffer.range(range.clone()).map(|s| [s.x, s.y]).collect();
+        let extreme = |points: &[[f64; 2]], pick: fn(f64, f64) -> f64| points.iter().map(|p| p[1]).reduce(pick);
+        assert_eq!(extreme(&points, f64::max), extreme(&expected, f64::max), "{:?}", range);
+        assert_eq!(extreme(&points, f64::min), extreme(&expected, f64::min), "{:?}", range);
+        assert!(points.windows(2).all(|w| w[0][0] <= w[1][0]));
+        let first_x = buffer.first().map_or(0.0, |s| s.x);
+        for point in &points {
+            let index = point[0] - first_x;
+            assert!(range.contains(&(index as usize)), "{:?} {:?}", point, range);
+            assert_eq!(buffer.get(index as usize).map(|s| s.y), Some(point[1]));
+        }
+        assert!(points.len() <= buckets.max(1) * 2 + 4);
+    }
+
+    #[test]
+    fn decimate_keeps_min_and_max() {
+        let buffer = filled(200_000, HistoryLimit::Points(1_000_000));
+        for range in [0..200_000, 1234..87_654, 9_990..10_010, 17..18, 150_000..199_999] {
+            for buckets in [1, 7, 100, 1000] {
+                check_decimate(&buffer, range.clone(), buckets);
+            }
+        }
+    }
+
+    #[test]
+    fn cache_follows_trim_by_points() {
+        let mut buffer = SampleBuffer::default();
+        for i in 0..300_000 {
+            buffer.push(sample(i), HistoryLimit::Points(50_001));
+            if i % 49_999 == 0 {
+                check_decimate(&buffer, 0..buffer.len(), 64);
+            }
+        }
+        assert_eq!(buffer.len(), 50_001);
+        assert_eq!(buffer.first().map(|s| s.x), Some(249_999.0));
+        check_decimate(&buffer, 0..buffer.len(), 64);
+        check_decimate(&buffer, 1..buffer.len() - 1, 10);
+
+        // 缩短历史长度后，已丢弃的尖峰不能留在缓存中
+        buffer.trim(HistoryLimit::Points(20_000));
+        check_decimate(&buffer, 0..buffer.len(), 64);
+    }
+
+    #[test]
+    fn cache_follows_trim_by_seconds() {
+        let mut buffer = filled(100_000, HistoryLimit::Seconds(30.0));
+        assert_eq!(buffer.len(), 30_001);
+        check_decimate(&buffer, 0..buffer.len(), 50);
+        buffer.trim(HistoryLimit::Seconds(0.5));
+        assert_eq!(buffer.len(), 501);
+        check_decimate(&buffer, 0..buffer.len(), 50);
+    }
+
+    #[test]
+    fn cache_coarsens_when_full() {
+        let buffer = filled(MIN_MAX_MAX_BUCKETS * MIN_MAX_BUCKET_SIZE * 3, HistoryLimit::Points(MAX_HISTORY_POINTS));
+        assert!(buffer.min_max.buckets.len() <= MIN_MAX_MAX_BUCKETS);
+        assert!(buffer.min_max.bucket_size > MIN_MAX_BUCKET_SIZE);
+        check_decimate(&buffer, 0..buffer.len(), 100);
+        check_decimate(&buffer, 333..buffer.len() - 333, 100);
+    }
+
+    #[test]
+    fn cache_resets_on_clear() {
+        let mut buffer = filled(50_000, HistoryLimit::Points(10_000));
+        buffer.clear();
+        assert!(buffer.decimate(0..0, 100, XAxisMode::Index).is_empty());
+        for i in 0..40_000 {
+            buffer.push(Sample { x: i as f64, y: 0.5, t: 0.0 }, HistoryLimit::Points(10_000));
+        }
+        let points = buffer.decimate(0..buffer.len(), 10, XAxisMode::Index);
+        assert!(points.iter().all(|p| p[1] == 0.5));
+    }
+
+    #[test]
+    fn decimate_empty_and_single_sample() {
+        let mut buffer = SampleBuffer::default();
+        assert!(buffer.decimate(0..0, 100, XAxisMode::Index).is_empty());
+        assert!(buffer.decimate(0..0, 0, XAxisMode::Relative).is_empty());
+
+        buffer.push(Sample { x: 3.0, y: 4.0, t: 5.0 }, HistoryLimit::Points(1));
+        assert_eq!(buffer.decimate(0..1, 100, XAxisMode::Index), vec![[3.0, 4.0]]);
+        assert_eq!(buffer.decimate(0..1, 0, XAxisMode::Relative), vec![[5.0, 4.0]]);
+        buffer.push(Sample { x: 4.0, y: 1.0, t: 6.0 }, HistoryLimit::Points(1));
+        assert_eq!(buffer.len(), 1);
+        assert_eq!(buffer.decimate(0..1, 1, XAxisMode::Index), vec![[4.0, 1.0]]);
+    }
+}
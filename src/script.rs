@@ -0,0 +1,812 @@
+use mlua::{ChunkMode, Function, HookTriggers, Lua, LuaOptions, StdLib, Table, Value, Variadic, VmState};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime};
+
+// 控制台最多保留的行数
+const CONSOLE_MAX_LINES: usize = 1000;
+// 检查脚本文件是否被修改的间隔
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+// 每执行多少条虚拟机指令检查一次是否超时
+const HOOK_INSTRUCTION_INTERVAL: u32 = 1000;
+// 加载脚本(执行顶层代码)允许的时间是单次调用的倍数
+const LOAD_BUDGET_FACTOR: u32 = 10;
+// 基础库中可以访问文件系统的函数
+const UNSAFE_GLOBALS: [&str; 3] = ["dofile", "loadfile", "require"];
+
+// 脚本错误信息，尽量解析出出错的行号和该行源码
+pub struct ScriptError {
+    pub message: String,
+    pub line: Option<usize>,
+    pub source_line: Option<String>,
+    pub count: usize,
+}
+
+// parse_waveform 返回的一个数据点，没有指定 X 时使用通道的采样序号
+pub struct ParsedSample {
+    pub channel: usize,
+    pub x: Option<f64>,
+    pub y: f64,
+}
+
+// parse_waveform 为通道提供的名称和单位
+pub struct ParsedChannelInfo {
+    pub channel: usize,
+    pub name: Option<String>,
+    pub unit: Option<String>,
+}
+
+// parse_waveform 返回的一帧解析结果
+#[derive(Default)]
+pub struct ParsedFrame {
+    pub samples: Vec<ParsedSample>,
+    pub channel_info: Vec<ParsedChannelInfo>,
+    pub fields: Vec<(String, String)>,
+}
+
+impl ParsedFrame {
+    // 支持的返回格式：
+    //   {channel = 1, points = {...}}                     一个通道多个点
+    //   {ch = 0, y = 1.5, x = 100}                        单个点，x 或设备时间戳 t 可选
+    //   {t = 12.5, {ch = 0, y = 1}, {ch = 1, y = 2}}      一帧多个通道，外层的 x/t 作用于各通道
+    //   各通道可附带 name = "accel_x", unit = "g"         通道名称和单位
+    //   fields = {temp = 25.0, mode = "run"}              附加的命名字段
+    pub fn from_table(table: &Table) -> mlua::Result<Self> {
+        let mut frame = ParsedFrame::default();
+        let frame_x = sample_x(table)?;
+
+        if table.contains_key("ch")? || table.contains_key("channel")? {
+            frame.push_entry(table, frame_x)?;
+        }
+        for entry in table.sequence_values::<Table>() {
+            frame.push_entry(&entry?, frame_x)?;
+        }
+
+        if let Some(fields) = table.get::<Option<Table>>("fields")? {
+            for pair in fields.pairs::<String, Value>() {
+                let (name, value) = pair?;
+                frame.fields.push((name, readout_text(&value)));
+            }
+            frame.fields.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+
+        Ok(frame)
+    }
+
+    fn push_entry(&mut self, entry: &Table, frame_x: Option<f64>) -> mlua::Result<()> {
+        let channel = match entry.get::<Option<usize>>("ch")? {
+            Some(channel) => channel,
+            None => entry.get::<Option<usize>>("channel")?.ok_or_else(|| {
+                mlua::Error::RuntimeError("parse_waveform 返回的数据缺少通道号 ch".to_string())
+            })?,
+        };
+        let x = sample_x(entry)?.or(frame_x);
+
+        let name = entry.get::<Option<String>>("name")?;
+        let unit = entry.get::<Option<String>>("unit")?;
+        if name.is_some() || unit.is_some() {
+            self.channel_info.push(ParsedChannelInfo { channel, name, unit });
+        }
+
+        if let Some(y) = entry.get::<Option<f64>>("y")? {
+            self.samples.push(ParsedSample { channel, x, y });
+        }
+        if let Some(points) = entry.get::<Option<Table>>("points")? {
+            // 多个点共用一个 X 时按 dx 递增
+            let dx = entry.get::<Option<f64>>("dx")?.unwrap_or(1.0);
+            for (i, y) in points.sequence_values::<f64>().enumerate() {
+                let x = x.map(|x| x + i as f64 * dx);
+                self.samples.push(ParsedSample { channel, x, y: y? });
+            }
+        }
+        Ok(())
+    }
+}
+
+// 数据点的 X：优先使用 x，其次使用设备时间戳 t
+fn sample_x(table: &Table) -> mlua::Result<Option<f64>> {
+    match table.get::<Option<f64>>("x")? {
+        Some(x) => Ok(Some(x)),
+        None => table.get::<Option<f64>>("t"),
+    }
+}
+
+struct ScriptTimer {
+    id: u32,
+    interval: Duration,
+    next: Instant,
+    repeat: bool,
+    callback: Function,
+}
+
+// 脚本通过API请求的操作，由主程序在每次调用脚本后取出执行
+#[derive(Default)]
+pub struct ScriptActions {
+    pub outgoing: Vec<Vec<u8>>,           // send / send_hex 要发送的数据
+    pub status: Option<String>,           // set_status 设置的状态栏信息
+    pub plot_points: Vec<(usize, f64)>,   // plot 添加的波形数据点
+    pub plot_clears: Vec<usize>,          // plot_clear 要清空的通道
+}
+
+// 脚本注册的控件，显示在主窗口右侧的脚本面板中
+pub enum ScriptWidgetKind {
+    Button,
+    Slider { min: f64, max: f64, value: f64 },
+    Checkbox { checked: bool },
+    Readout { value: String },
+    Separator,
+}
+
+pub struct ScriptWidget {
+    pub label: String,
+    pub kind: ScriptWidgetKind,
+    callback: Option<Function>,
+}
+
+// 脚本API和主程序之间共享的状态
+#[derive(Default)]
+pub struct ScriptHost {
+    pub actions: ScriptActions,
+    pub channel_values: HashMap<usize, f64>, // 各通道最新的数值，供 plot_value 读取
+    pub widgets: Vec<ScriptWidget>,
+    timers: Vec<ScriptTimer>,
+    next_timer_id: u32,
+    receive_handler: Option<Function>,
+}
+
+impl ScriptHost {
+    // 脚本注册了定时器、控件或接收回调，可能通过 plot_value 读取通道数据
+    pub fn has_callbacks(&self) -> bool {
+        !self.timers.is_empty() || !self.widgets.is_empty() || self.receive_handler.is_some()
+    }
+
+    // 脚本重新加载或停用时清除注册的回调和控件
+    fn reset(&mut self) {
+        self.timers.clear();
+        self.receive_handler = None;
+        self.widgets.clear();
+    }
+
+    // 同名控件重复注册时替换原有控件，保持原来的位置
+    fn add_widget(&mut self, label: String, kind: ScriptWidgetKind, callback: Option<Function>) {
+        let widget = ScriptWidget { label, kind, callback };
+        let existing = self.widgets.iter_mut().find(|w| {
+            !matches!(w.kind, ScriptWidgetKind::Separator) && w.label == widget.label
+        });
+        match existing {
+            Some(existing) => *existing = widget,
+            None => self.widgets.push(widget),
+        }
+    }
+}
+
+pub struct LuaScript {
+    pub path: String,
+    pub lua: Option<Lua>,
+    pub error: Option<ScriptError>,
+    pub console: Rc<RefCell<VecDeque<String>>>,
+    pub auto_reload: bool,
+    pub time_budget_ms: u64,    // 单次调用允许的最长执行时间
+    pub memory_limit_mb: usize, // 脚本可使用的最大内存
+    pub disabled: bool,         // 超出限制后脚本被停用，修改文件或手动重新加载后恢复
+    pub host: Rc<RefCell<ScriptHost>>,
+    source: String,
+    modified: Option<SystemTime>,
+    last_check: Instant,
+    deadline: Rc<Cell<Option<Instant>>>,
+    timed_out: Rc<Cell<bool>>,
+    out_of_memory: Rc<Cell<bool>>, // 内存错误被 pcall 捕获后重新抛出时，错误类型已变为普通错误
+}
+
+impl LuaScript {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            lua: None,
+            error: None,
+            console: Rc::new(RefCell::new(VecDeque::new())),
+            auto_reload: true,
+            time_budget_ms: 50,
+            memory_limit_mb: 32,
+            disabled: false,
+            host: Rc::new(RefCell::new(ScriptHost::default())),
+            source: String::new(),
+            modified: None,
+            last_check: Instant::now(),
+            deadline: Rc::new(Cell::new(None)),
+            timed_out: Rc::new(Cell::new(false)),
+            out_of_memory: Rc::new(Cell::new(false)),
+        }
+    }
+
+    // 首次使用时加载脚本，加载失败后不再重复尝试，等待文件修改或手动重新加载
+    pub fn ensure_loaded(&mut self) {
+        if self.lua.is_none() && self.error.is_none() {
+            self.load();
+        }
+    }
+
+    // 切换到新的脚本文件
+    pub fn set_path(&mut self, path: &str) {
+        self.path = path.to_string();
+        self.load();
+    }
+
+    // (重新)加载脚本，失败时记录错误而不是 panic
+    pub fn load(&mut self) {
+        self.lua = None;
+        self.error = None;
+        self.disabled = false;
+        self.host.borrow_mut().reset();
+        self.modified = file_modified(&self.path);
+        self.last_check = Instant::now();
+
+        self.source = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) => {
+                self.set_error(format!("无法读取Lua脚本文件 {}: {}", self.path, e));
+                return;
+            }
+        };
+
+        let lua = match self.create_sandbox() {
+            Ok(lua) => lua,
+            Err(e) => {
+                self.report_error(&e);
+                return;
+            }
+        };
+
+        // 以 @文件名 作为块名，错误信息会显示为 "文件名:行号: 信息"
+        let chunk = lua.load(&self.source).set_name(format!("@{}", self.chunk_name())).set_mode(ChunkMode::Text);
+        let budget = self.time_budget() * LOAD_BUDGET_FACTOR;
+        if let Err(e) = self.run_limited(budget, || chunk.exec()) {
+            self.handle_call_error(&e, budget);
+            return;
+        }
+
+        self.lua = Some(lua);
+        self.log(format!("脚本已加载: {}", self.path));
+    }
+
+    // 在时间和内存限制下调用脚本，出错时记录错误，超出限制时停用脚本
+    pub fn call<R>(&mut self, f: impl FnOnce(&Lua) -> mlua::Result<R>) -> Option<R> {
+        let lua = self.lua.clone()?;
+        let budget = self.time_budget();
+        match self.run_limited(budget, || f(&lua)) {
+            Ok(result) => Some(result),
+            Err(e) => {
+                self.handle_call_error(&e, budget);
+                None
+            }
+        }
+    }
+
+    // 定期检查脚本文件的修改时间，发生变化时自动重新加载
+    pub fn poll_reload(&mut self) {
+        if !self.auto_reload || self.last_check.elapsed() < RELOAD_CHECK_INTERVAL {
+            return;
+        }
+        self.last_check = Instant::now();
+
+        let modified = file_modified(&self.path);
+        if modified.is_some() && modified != self.modified {
+            self.log("检测到脚本修改，重新加载");
+            self.load();
+        }
+    }
+
+    // 把接收到的数据交给脚本中用 on_receive 注册的函数
+    pub fn on_receive(&mut self, data: &[u8]) {
+        let handler = self.host.borrow().receive_handler.clone();
+        if let Some(handler) = handler {
+            self.call(|lua| handler.call::<()>(lua.create_sequence_from(data.iter().copied())?));
+        }
+    }
+
+    // 执行到期的定时器回调
+    pub fn run_timers(&mut self) {
+        let now = Instant::now();
+        let mut due = Vec::new();
+        self.host.borrow_mut().timers.retain_mut(|timer| {
+            if timer.next > now {
+                return true;
+            }
+            due.push(timer.callback.clone());
+            timer.next = now + timer.interval;
+            timer.repeat
+        });
+
+        for callback in due {
+            if self.lua.is_none() {
+                break;
+            }
+            self.call(|_| callback.call::<()>(()));
+        }
+    }
+
+    // 脚本面板中的控件被操作后，以控件当前的值调用其回调函数
+    pub fn widget_changed(&mut self, index: usize) {
+        let (callback, value) = {
+            let host = self.host.borrow();
+            let Some(widget) = host.widgets.get(index) else {
+                return;
+            };
+            let value = match &widget.kind {
+                ScriptWidgetKind::Slider { value, .. } => Value::Number(*value),
+                ScriptWidgetKind::Checkbox { checked } => Value::Boolean(*checked),
+                _ => Value::Nil,
+            };
+            (widget.callback.clone(), value)
+        };
+
+        if let Some(callback) = callback {
+            self.call(|_| callback.call::<()>(value));
+        }
+    }
+
+    // 取出脚本请求的操作
+    pub fn take_actions(&mut self) -> ScriptActions {
+        std::mem::take(&mut self.host.borrow_mut().actions)
+    }
+
+    pub fn set_channel_value(&self, channel: usize, value: f64) {
+        self.host.borrow_mut().channel_values.insert(channel, value);
+    }
+
+    fn time_budget(&self) -> Duration {
+        Duration::from_millis(self.time_budget_ms.max(1))
+    }
+
+    fn run_limited<R>(&self, budget: Duration, f: impl FnOnce() -> mlua::Result<R>) -> mlua::Result<R> {
+        self.timed_out.set(false);
+        self.out_of_memory.set(false);
+        self.deadline.set(Some(Instant::now() + budget));
+        let result = f();
+        self.deadline.set(None);
+        result
+    }
+
+    fn handle_call_error(&mut self, err: &mlua::Error, budget: Duration) {
+        if self.timed_out.get() {
+            self.disable(format!("脚本执行超过 {} ms，可能存在死循环，已停用", budget.as_millis()));
+        } else if self.out_of_memory.get() || is_memory_error(err) {
+            self.disable(format!("脚本内存超过 {} MB 限制，已停用", self.memory_limit_mb));
+        } else {
+            self.report_error(err);
+        }
+    }
+
+    // 停用脚本，保留错误信息，等待修改文件或手动重新加载
+    fn disable(&mut self, message: String) {
+        self.lua = None;
+        self.disabled = true;
+        self.host.borrow_mut().reset();
+        self.error = None;
+        self.set_error(message);
+    }
+
+    // 创建受限的Lua环境：只加载安全的标准库，并设置内存限制和超时钩子
+    fn create_sandbox(&self) -> mlua::Result<Lua> {
+        // 不加载 io、os、package、debug 库
+        let libs = StdLib::TABLE | StdLib::STRING | StdLib::MATH | StdLib::UTF8 | StdLib::COROUTINE;
+        let lua = Lua::new_with(libs, LuaOptions::default())?;
+        lua.set_memory_limit(self.memory_limit_mb * 1024 * 1024)?;
+
+        let globals = lua.globals();
+        for name in UNSAFE_GLOBALS {
+            globals.set(name, Value::Nil)?;
+        }
+        // 预编译的字节码可以绕过沙箱：load 只接受源码，并去掉 string.dump
+        lua.load(
+            r#"
+            local raw_load = load
+            load = function(chunk, name, _, ...) return raw_load(chunk, name, "t", ...) end
+            string.dump = nil
+            "#,
+        )
+        .set_name("沙箱")
+        .exec()?;
+
+        // 只提供计时相关的 os 函数
+        let start = Instant::now();
+        let os = lua.create_table()?;
+        os.set("clock", lua.create_function(move |_, ()| Ok(start.elapsed().as_secs_f64()))?)?;
+        os.set("time", lua.create_function(|_, ()| Ok(chrono::Local::now().timestamp()))?)?;
+        globals.set("os", os)?;
+
+        self.install_print(&lua)?;
+        self.install_api(&lua)?;
+
+        let deadline = Rc::clone(&self.deadline);
+        let timed_out = Rc::clone(&self.timed_out);
+        lua.set_hook(
+            HookTriggers::new().every_nth_instruction(HOOK_INSTRUCTION_INTERVAL),
+            move |_, _| {
+                // 超时后本次调用中每次检查都报错，直到回到宿主
+                if timed_out.get() || deadline.get().is_some_and(|deadline| Instant::now() > deadline) {
+                    timed_out.set(true);
+                    Err(mlua::Error::RuntimeError("脚本执行超时".to_string()))
+                } else {
+                    Ok(VmState::Continue)
+                }
+            },
+        );
+        self.install_abort_guard(&lua)?;
+
+        Ok(lua)
+    }
+
+    // 超时和内存错误是普通的 Lua 错误，会被 pcall 捕获，脚本可以继续运行
+    // 包装 pcall、xpcall 和 coroutine.resume，遇到这两种错误时继续向外抛出
+    fn install_abort_guard(&self, lua: &Lua) -> mlua::Result<()> {
+        let timed_out = Rc::clone(&self.timed_out);
+        let out_of_memory = Rc::clone(&self.out_of_memory);
+        let aborted = lua.create_function(move |_, err: Value| {
+            if matches!(&err, Value::String(s) if s.to_str().is_ok_and(|s| s == "not enough memory")) {
+                out_of_memory.set(true);
+            }
+            Ok(timed_out.get() || out_of_memory.get())
+        })?;
+        lua.load(
+            r#"
+            local aborted = ...
+            local raw_pcall, raw_xpcall, raw_resume = pcall, xpcall, coroutine.resume
+            local function check(ok, ...)
+                if not ok and aborted((...)) then
+                    error((...), 0)
+                end
+                return ok, ...
+            end
+            pcall = function(...) return check(raw_pcall(...)) end
+            xpcall = function(...) return check(raw_xpcall(...)) end
+            coroutine.resume = function(...) return check(raw_resume(...)) end
+            "#,
+        )
+        .set_name("沙箱")
+        .call::<()>(aborted)
+    }
+
+    // 记录一条Lua错误，相同的错误只累加次数，避免每帧刷屏
+    pub fn report_error(&mut self, err: &mlua::Error) {
+        self.set_error(err.to_string());
+    }
+
+    pub fn clear_error(&mut self) {
+        self.error = None;
+    }
+
+    pub fn log(&self, msg: impl Into<String>) {
+        push_console_line(&self.console, msg.into());
+    }
+
+    fn set_error(&mut self, message: String) {
+        if let Some(error) = self.error.as_mut().filter(|e| e.message == message) {
+            error.count += 1;
+            return;
+        }
+
+        let line = parse_error_line(&message, &self.chunk_name());
+        let source_line = line
+            .and_then(|l| self.source.lines().nth(l.saturating_sub(1)))
+            .map(|s| s.trim_end().to_string());
+
+        self.log(format!("错误: {}", message.lines().next().unwrap_or_default()));
+        self.error = Some(ScriptError {
+            message,
+            line,
+            source_line,
+            count: 1,
+        });
+    }
+
+    // Lua 会截断过长的块名，因此只使用文件名
+    fn chunk_name(&self) -> String {
+        std::path::Path::new(&self.path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.path.clone())
+    }
+
+    // 将 print 重定向到程序内的控制台
+    fn install_print(&self, lua: &Lua) -> mlua::Result<()> {
+        let console = Rc::clone(&self.console);
+        let print = lua.create_function(move |_, args: Variadic<Value>| {
+            let line = args
+                .iter()
+                .map(|v| v.to_string().unwrap_or_else(|_| v.type_name().to_string()))
+                .collect::<Vec<_>>()
+                .join("\t");
+            push_console_line(&console, line);
+            Ok(())
+        })?;
+        lua.globals().set("print", print)
+    }
+
+    // 提供给脚本的API：收发数据、定时器、日志、状态栏和波形通道
+    fn install_api(&self, lua: &Lua) -> mlua::Result<()> {
+        let globals = lua.globals();
+
+        // send(data): data 可以是字符串或字节数组
+        let host = Rc::clone(&self.host);
+        globals.set("send", lua.create_function(move |_, data: Value| {
+            let bytes = match data {
+                Value::String(s) => s.as_bytes().to_vec(),
+                Value::Table(t) => t.sequence_values::<u8>().collect::<mlua::Result<Vec<u8>>>()?,
+                other => {
+                    return Err(mlua::Error::RuntimeError(format!(
+                        "send 需要字符串或字节数组，实际为 {}",
+                        other.type_name()
+                    )))
+                }
+            };
+            host.borrow_mut().actions.outgoing.push(bytes);
+            Ok(())
+        })?)?;
+
+        // send_hex("AA 01 02") 或 send_hex("AA0102")
+        let host = Rc::clone(&self.host);
+        globals.set("send_hex", lua.create_function(move |_, hex: String| {
+            let bytes = crate::utils::hex_to_bytes(&hex).map_err(mlua::Error::RuntimeError)?;
+            host.borrow_mut().actions.outgoing.push(bytes);
+            Ok(())
+        })?)?;
+
+        // set_timer(ms, fn, repeat) 返回定时器编号，repeat 默认为 true
+        let host = Rc::clone(&self.host);
+        globals.set("set_timer", lua.create_function(move |_, (ms, callback, repeat): (u64, Function, Option<bool>)| {
+            let mut host = host.borrow_mut();
+            host.next_timer_id += 1;
+            let id = host.next_timer_id;
+            let interval = Duration::from_millis(ms.max(1));
+            host.timers.push(ScriptTimer {
+                id,
+                interval,
+                next: Instant::now() + interval,
+                repeat: repeat.unwrap_or(true),
+                callback,
+            });
+            Ok(id)
+        })?)?;
+
+        let host = Rc::clone(&self.host);
+        globals.set("clear_timer", lua.create_function(move |_, id: u32| {
+            host.borrow_mut().timers.retain(|timer| timer.id != id);
+            Ok(())
+        })?)?;
+
+        // on_receive(fn)：每收到一段数据调用 fn(bytes)
+        let host = Rc::clone(&self.host);
+        globals.set("on_receive", lua.create_function(move |_, handler: Option<Function>| {
+            host.borrow_mut().receive_handler = handler;
+            Ok(())
+        })?)?;
+
+        // log(msg)：带时间戳输出到控制台
+        let console = Rc::clone(&self.console);
+        globals.set("log", lua.create_function(move |_, msg: Value| {
+            let msg = msg.to_string().unwrap_or_else(|_| msg.type_name().to_string());
+            let timestamp = chrono::Local::now().format("%H:%M:%S%.3f");
+            push_console_line(&console, format!("[{}] {}", timestamp, msg));
+            Ok(())
+        })?)?;
+
+        let host = Rc::clone(&self.host);
+        globals.set("set_status", lua.create_function(move |_, msg: Option<String>| {
+            host.borrow_mut().actions.status = Some(msg.unwrap_or_default());
+            Ok(())
+        })?)?;
+
+        // plot(ch, value)：向波形通道添加一个数据点
+        let host = Rc::clone(&self.host);
+        globals.set("plot", lua.create_function(move |_, (channel, value): (usize, f64)| {
+            host.borrow_mut().actions.plot_points.push((channel, value));
+            Ok(())
+        })?)?;
+
+        // plot_value(ch)：读取通道最新的数值，没有数据时返回 nil
+        let host = Rc::clone(&self.host);
+        globals.set("plot_value", lua.create_function(move |_, channel: usize| {
+            Ok(host.borrow().channel_values.get(&channel).copied())
+        })?)?;
+
+        let host = Rc::clone(&self.host);
+        globals.set("plot_clear", lua.create_function(move |_, channel: usize| {
+            let mut host = host.borrow_mut();
+            host.channel_values.remove(&channel);
+            host.actions.plot_clears.push(channel);
+            Ok(())
+        })?)?;
+
+        self.install_ui_api(lua)
+    }
+
+    // 脚本面板控件API：按钮、滑块、复选框和数值显示
+    fn install_ui_api(&self, lua: &Lua) -> mlua::Result<()> {
+        let globals = lua.globals();
+
+        // ui_button(label, fn)
+        let host = Rc::clone(&self.host);
+        globals.set("ui_button", lua.create_function(move |_, (label, callback): (String, Function)| {
+            host.borrow_mut().add_widget(label, ScriptWidgetKind::Button, Some(callback));
+            Ok(())
+        })?)?;
+
+        // ui_slider(label, min, max, fn, initial)：拖动时调用 fn(value)
+        let host = Rc::clone(&self.host);
+        globals.set("ui_slider", lua.create_function(
+            move |_, (label, min, max, callback, initial): (String, f64, f64, Option<Function>, Option<f64>)| {
+                let (min, max) = if min <= max { (min, max) } else { (max, min) };
+                let value = initial.unwrap_or(min).clamp(min, max);
+                host.borrow_mut().add_widget(label, ScriptWidgetKind::Slider { min, max, value }, callback);
+                Ok(())
+            },
+        )?)?;
+
+        // ui_checkbox(label, fn, initial)：切换时调用 fn(checked)
+        let host = Rc::clone(&self.host);
+        globals.set("ui_checkbox", lua.create_function(
+            move |_, (label, callback, initial): (String, Option<Function>, Option<bool>)| {
+                let checked = initial.unwrap_or(false);
+                host.borrow_mut().add_widget(label, ScriptWidgetKind::Checkbox { checked }, callback);
+                Ok(())
+            },
+        )?)?;
+
+        // ui_label(label, value)：带名称的数值显示，之后用 ui_set 更新
+        let host = Rc::clone(&self.host);
+        globals.set("ui_label", lua.create_function(move |_, (label, value): (String, Value)| {
+            let value = readout_text(&value);
+            host.borrow_mut().add_widget(label, ScriptWidgetKind::Readout { value }, None);
+            Ok(())
+        })?)?;
+
+        let host = Rc::clone(&self.host);
+        globals.set("ui_separator", lua.create_function(move |_, ()| {
+            host.borrow_mut().add_widget(String::new(), ScriptWidgetKind::Separator, None);
+            Ok(())
+        })?)?;
+
+        // ui_set(label, value)：更新数值显示、滑块或复选框的值，不触发回调
+        let host = Rc::clone(&self.host);
+        globals.set("ui_set", lua.create_function(move |_, (label, value): (String, Value)| {
+            let mut host = host.borrow_mut();
+            let widget = host.widgets.iter_mut().find(|w| w.label == label).ok_or_else(|| {
+                mlua::Error::RuntimeError(format!("没有名为 {} 的控件", label))
+            })?;
+            match &mut widget.kind {
+                ScriptWidgetKind::Readout { value: text } => *text = readout_text(&value),
+                ScriptWidgetKind::Slider { min, max, value: current } => {
+                    if let Some(v) = value.as_f64().or_else(|| value.as_i64().map(|v| v as f64)) {
+                        *current = v.clamp(*min, *max);
+                    }
+                }
+                ScriptWidgetKind::Checkbox { checked } => *checked = value.as_boolean().unwrap_or(false),
+                _ => {}
+            }
+            Ok(())
+        })?)?;
+
+        let host = Rc::clone(&self.host);
+        globals.set("ui_clear", lua.create_function(move |_, ()| {
+            host.borrow_mut().widgets.clear();
+            Ok(())
+        })?)?;
+
+        Ok(())
+    }
+}
+
+// 数值显示控件的文本，小数保留4位
+fn readout_text(value: &Value) -> String {
+    match value {
+        Value::Nil => "-".to_string(),
+        Value::Number(n) => format!("{:.4}", n),
+        other => other.to_string().unwrap_or_else(|_| other.type_name().to_string()),
+    }
+}
+
+fn push_console_line(console: &RefCell<VecDeque<String>>, line: String) {
+    let mut console = console.borrow_mut();
+    console.push_back(line);
+    while console.len() > CONSOLE_MAX_LINES {
+        console.pop_front();
+    }
+}
+
+fn is_memory_error(err: &mlua::Error) -> bool {
+    match err {
+        mlua::Error::MemoryError(_) => true,
+        mlua::Error::CallbackError { cause, .. } => is_memory_error(cause),
+        _ => false,
+    }
+}
+
+fn file_modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// 从 "文件名:行号: 信息" 格式的错误中解析行号
+fn parse_error_line(message: &str, chunk_name: &str) -> Option<usize> {
+    let pattern = format!("{}:", chunk_name);
+    message.match_indices(&pattern).find_map(|(pos, _)| {
+        let rest = &message[pos + pattern.len()..];
+        let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if rest[digits.len()..].starts_with(':') {
+            digits.parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_script(name: &str, source: &str) -> LuaScript {
+        let path = std::env::temp_dir().join(format!("{}_{}.lua", name, std::process::id()));
+        std::fs::write(&path, source).unwrap();
+        let mut script = LuaScript::new(path.to_str().unwrap());
+        script.time_budget_ms = 20;
+        script.load();
+        let _ = std::fs::remove_file(&path);
+        script
+    }
+
+    #[test]
+    fn pcall_does_not_catch_timeout() {
+        let mut script = load_script(
+            "pcall_timeout",
+            "function run() while true do pcall(function() while true do end end) end end",
+        );
+        assert!(script.lua.is_some());
+        let started = Instant::now();
+        let result = script.call(|lua| lua.globals().get::<Function>("run")?.call::<()>(()));
+        assert!(result.is_none());
+        assert!(script.disabled);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn pcall_does_not_catch_timeout_while_loading() {
+        let script = load_script("pcall_timeout_load", "while true do xpcall(function() while true do end end, print) end");
+        assert!(script.lua.is_none());
+        assert!(script.disabled);
+    }
+
+    #[test]
+    fn pcall_does_not_catch_memory_error() {
+        let mut script = load_script(
+            "pcall_memory",
+            "function run() local t = {} while true do pcall(function() for i = 1, 1e9 do t[#t + 1] = string.rep('x', 1024) .. i end end) end end",
+        );
+        script.time_budget_ms = 10_000;
+        let result = script.call(|lua| lua.globals().get::<Function>("run")?.call::<()>(()));
+        assert!(result.is_none());
+        assert!(script.disabled);
+        assert!(script.error.as_ref().is_some_and(|e| e.message.contains("内存")));
+    }
+
+    #[test]
+    fn load_rejects_binary_chunks() {
+        let mut script = load_script(
+            "load_binary",
+            r"function run() local ok, err = load('\27Lua\84\0', 'x', 'b') return ok == nil and string.dump == nil, tostring(err) end",
+        );
+        let result = script.call(|lua| lua.globals().get::<Function>("run")?.call::<(bool, String)>(()));
+        let (rejected, message) = result.unwrap();
+        assert!(rejected);
+        assert!(message.contains("binary"), "{}", message);
+        assert_eq!(script.call(|lua| lua.load("return load('return 1')()").eval::<i32>()), Some(1));
+    }
+
+    #[test]
+    fn pcall_still_catches_script_errors() {
+        let mut script = load_script("pcall_error", "function run() local ok = pcall(error, 'x') return ok end");
+        let result = script.call(|lua| lua.globals().get::<Function>("run")?.call::<bool>(()));
+        assert_eq!(result, Some(false));
+        assert!(!script.disabled);
+    }
+}
@@ -9,6 +9,12 @@ pub struct SharedData {
     pub plot_data: Vec<(f64, f64)>,
 }
 
+// 读取线程收到的一段数据，时间戳在读取线程中记录
+pub struct RxChunk {
+    pub time: chrono::DateTime<chrono::Local>,
+    pub data: Vec<u8>,
+}
+
 pub struct SerialPortHandle {
     pub(crate) port: Arc<Mutex<Box<dyn SerialPort>>>,
     pub(crate) running: Arc<Mutex<bool>>,
@@ -30,7 +36,7 @@ pub fn open_port(
     data_bits: serialport::DataBits,
     stop_bits: serialport::StopBits,
     parity: serialport::Parity,
-    received_data_shared: Arc<Mutex<String>>,
+    received_data_shared: Arc<Mutex<Vec<RxChunk>>>,
 ) -> Option<SerialPortHandle> {
     if let Ok(port) = serialport::new(port_name, baud_rate)
         .data_bits(data_bits)
@@ -54,11 +60,12 @@ pub fn open_port(
                     Ok(mut port) => {
                         match port.read(&mut buf) {
                             Ok(bytes_read) if bytes_read > 0 => {
+                                let chunk = RxChunk {
+                                    time: chrono::Local::now(),
+                                    data: buf[..bytes_read].to_vec(),
+                                };
                                 if let Ok(mut received) = received_data_clone.lock() {
-                                    for byte in &buf[..bytes_read] {
-                                        received.push(char::from_u32(0xFF00 + *byte as u32).unwrap_or('?'));
-                                    }
-                                    received.push('\n');
+                                    received.push(chunk);
                                 }
                             },
                             Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {},
@@ -0,0 +1,399 @@
+use crate::channel::PlotChannel;
+use crate::sample_buffer::{Sample, XAxisMode};
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
+
+// 自动模式下超过这个时间没有触发就自由运行，显示最新的一段波形
+const AUTO_TIMEOUT: Duration = Duration::from_millis(500);
+
+// 触发模式：自动(超时未触发时自由运行)、常规(只显示触发的波形)、单次(触发一次后停止)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TriggerMode {
+    Off,
+    Auto,
+    Normal,
+    Single,
+}
+
+impl TriggerMode {
+    pub const ALL: [TriggerMode; 4] = [TriggerMode::Off, TriggerMode::Auto, TriggerMode::Normal, TriggerMode::Single];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TriggerMode::Off => "关闭",
+            TriggerMode::Auto => "自动",
+            TriggerMode::Normal => "常规",
+            TriggerMode::Single => "单次",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TriggerEdge {
+    Rising,
+    Falling,
+}
+
+impl TriggerEdge {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TriggerEdge::Rising => "上升沿",
+            TriggerEdge::Falling => "下降沿",
+        }
+    }
+}
+
+// 触发后冻结的一段波形，横坐标以触发点为 0
+pub struct TriggerCapture {
+    pub trigger_x: f64, // 触发点在原始横轴上的位置
+    pub trigger_t: f64, // 触发点的到达时间(相对采集开始的秒数)
+    pub forced: bool,   // 自动模式下超时未触发而强制显示
+    pub traces: Vec<(usize, Vec<[f64; 2]>)>,
+}
+
+pub struct Trigger {
+    pub mode: TriggerMode,
+    pub source: usize, // 触发源通道号
+    pub edge: TriggerEdge,
+    pub level: f64,
+    pub pre_trigger: f64, // 触发点之前的数据占窗口的百分比
+    pub window: f64,      // 捕获窗口宽度，单位与横轴相同(点数或秒)
+    pub capture: Option<TriggerCapture>,
+    pending: Option<(f64, f64)>, // 已触发、等待触发后数据的 (触发点横坐标, 触发点时间)
+    scan_x: Option<f64>,         // 已检查过的最大横坐标
+    last_fire: Instant,
+    stopped: bool,               // 单次模式已触发
+    new_capture: bool,           // 有新的捕获，视图需要重新缩放
+}
+
+impl Default for Trigger {
+    fn default() -> Self {
+        Self {
+            mode: TriggerMode::Off,
+            source: 0,
+            edge: TriggerEdge::Rising,
+            level: 0.0,
+            pre_trigger: 20.0,
+            window: 500.0,
+            capture: None,
+            pending: None,
+            scan_x: None,
+            last_fire: Instant::now(),
+            stopped: false,
+            new_capture: false,
+        }
+    }
+}
+
+impl Trigger {
+    pub fn is_active(&self) -> bool {
+        self.mode != TriggerMode::Off
+    }
+
+    // 清除已捕获的波形并重新等待触发，只检查之后到达的数据
+    pub fn rearm(&mut self) {
+        self.capture = None;
+        self.pending = None;
+        self.scan_x = None;
+        self.stopped = false;
+        self.last_fire = Instant::now();
+    }
+
+    // 返回是否有尚未显示过的新捕获
+    pub fn take_new_capture(&mut self) -> bool {
+        std::mem::take(&mut self.new_capture)
+    }
+
+    pub fn status_text(&self) -> &'static str {
+        if self.stopped {
+            "已触发(单次)"
+        } else if self.pending.is_some() {
+            "已触发, 采集中"
+        } else {
+            match &self.capture {
+                Some(capture) if capture.forced => "自动(未触发)",
+                Some(_) => "已触发",
+                None => "等待触发",
+            }
+        }
+    }
+
+    fn crosses(&self, previous: f64, current: f64) -> bool {
+        match self.edge {
+            TriggerEdge::Rising => previous < self.level && current >= self.level,
+            TriggerEdge::Falling => previous > self.level && current <= self.level,
+        }
+    }
+
+    // 检查触发源的新数据，触发点之后的数据采集够一个窗口后冻结所有通道的波形
+    pub fn update(&mut self, channels: &BTreeMap<usize, PlotChannel>, mode: XAxisMode, buckets: usize) {
+        if !self.is_active() || self.stopped {
+            return;
+        }
+        let Some(source) = channels.get(&self.source) else {
+            return;
+        };
+        let Some(newest) = source.data.last().map(|s| s.x_on(mode)) else {
+            return;
+        };
+        let pre = self.window * self.pre_trigger.clamp(0.0, 100.0) / 100.0;
+        let post = self.window - pre;
+
+        // 数据被清空后从头开始
+        if self.scan_x.is_some_and(|x| x > newest) {
+            self.scan_x = None;
+            self.pending = None;
+        }
+
+        if self.pending.is_none() {
+            let start = match self.scan_x {
+                Some(x) => source.data.first_after(x, mode),
+                None => source.data.len(),
+            };
+            let mut previous: Option<Sample> = start.checked_sub(1).and_then(|i| source.data.get(i)).copied();
+            let mut found = None;
+            for sample in source.data.range(start..source.data.len()) {
+                if let Some(previous) = previous
+                    && self.crosses(previous.y, sample.y)
+                {
+                    found = Some(*sample);
+                    break;
+                }
+                previous = Some(*sample);
+            }
+            match found {
+                Some(sample) => {
+                    self.pending = Some((sample.x_on(mode), sample.t));
+                    self.scan_x = Some(sample.x_on(mode));
+                }
+                None => self.scan_x = Some(newest),
+            }
+        }
+
+        if let Some((trigger_x, trigger_t)) = self.pending {
+            if newest >= trigger_x + post {
+                self.capture = Some(snapshot(channels, mode, trigger_x - pre..=trigger_x + post, trigger_x, trigger_t, buckets, false));
+                self.pending = None;
+                // 下一次从本次窗口结束后开始查找，避免同一段波形重复触发
+                self.scan_x = Some(trigger_x + post);
+                self.last_fire = Instant::now();
+                self.new_capture = true;
+                if self.mode == TriggerMode::Single {
+                    self.stopped = true;
+                }
+            }
+        } else if self.mode == TriggerMode::Auto && self.last_fire.elapsed() > AUTO_TIMEOUT {
+            let trigger_x = newest - post;
+            let index = source.data.first_after(trigger_x, mode).min(source.data.len() - 1);
+            let trigger_t = source.data.get(index).map_or(0.0, |s| s.t);
+            let was_forced = self.capture.as_ref().is_some_and(|c| c.forced);
+            self.capture = Some(snapshot(channels, mode, trigger_x - pre..=trigger_x + post, trigger_x, trigger_t, buckets, true));
+            self.new_capture |= !was_forced;
+        }
+    }
+}
+
+// 截取窗口内各通道的数据，横坐标平移到以触发点为 0
+fn snapshot(
+    channels: &BTreeMap<usize, PlotChannel>,
+    mode: XAxisMode,
+    window: RangeInclusive<f64>,
+    trigger_x: f64,
+    trigger_t: f64,
+    buckets: usize,
+    forced: bool,
+) -> TriggerCapture {
+    let traces = channels
+        .iter()
+        .map(|(index, channel)| {
+            let range = channel.data.index_range(*window.start(), *window.end(), mode);
+            let points = channel
+                .data
+                .decimate(range, buckets, mode)
+                .into_iter()
+                .map(|[x, y]| [x - trigger_x, y])
+                .collect();
+            (*index, points)
+        })
+        .collect();
+    TriggerCapture { trigger_x, trigger_t, forced, traces }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample_buffer::HistoryLimit;
+
+    // 触发源通道 0 添加下标 [from, to) 的数据，X 为下标，时间为下标 × 0.01 秒
+    fn push(channels: &mut BTreeMap<usize, PlotChannel>, from: usize, to: usize, y: impl Fn(usize) -> f64) {
+        let channel = channels.entry(0).or_insert_with(|| PlotChannel::new(0));
+        for i in from..to {
+            channel.data.push(Sample { x: i as f64, y: y(i), t: i as f64 * 0.01 }, HistoryLimit::Points(10_000));
+        }
+    }
+
+    fn trigger(mode: TriggerMode, edge: TriggerEdge, window: f64, pre_trigger: f64) -> Trigger {
+        Trigger { mode, edge, level: 0.5, window, pre_trigger, ..Trigger::default() }
+    }
+
+    fn trigger_x(trigger: &Trigger) -> Option<f64> {
+        trigger.capture.as_ref().map(|c| c.trigger_x)
+    }
+
+    // 每 10 个点一个周期的方波，上升沿在 5、15、25 ...
+    fn square(i: usize) -> f64 {
+        if i % 10 >= 5 { 1.0 } else { 0.0 }
+    }
+
+    #[test]
+    fn captures_window_around_rising_edge() {
+        let mut channels = BTreeMap::new();
+        let mut trigger = trigger(TriggerMode::Normal, TriggerEdge::Rising, 20.0, 25.0);
+        let step = |i: usize| if i >= 50 { 1.0 } else { 0.0 };
+
+        // 开始触发前已有的数据不检查
+        push(&mut channels, 0, 10, |_| 1.0);
+        trigger.update(&channels, XAxisMode::Index, 1000);
+        push(&mut channels, 10, 60, step);
+        trigger.update(&channels, XAxisMode::Index, 1000);
+        assert!(trigger.capture.is_none());
+        assert_eq!(trigger.status_text(), "已触发, 采集中");
+
+        // 触发点之后的数据够 15 个点才冻结
+        push(&mut channels, 60, 70, step);
+        trigger.update(&channels, XAxisMode::Index, 1000);
+        let capture = trigger.capture.as_ref().unwrap();
+        assert_eq!((capture.trigger_x, capture.trigger_t, capture.forced), (50.0, 0.5, false));
+        let (_, points) = &capture.traces[0];
+        // 窗口 [-5, 15]，两侧各多一个点
+        assert_eq!(points.first(), Some(&[-6.0, 0.0]));
+        assert_eq!(points.last(), Some(&[16.0, 1.0]));
+        assert!(points.contains(&[-1.0, 0.0]) && points.contains(&[0.0, 1.0]));
+        assert!(trigger.take_new_capture());
+        assert!(!trigger.take_new_capture());
+        assert_eq!(trigger.status_text(), "已触发");
+    }
+
+    #[test]
+    fn pre_trigger_ratio_moves_window() {
+        for (pre_trigger, first, last) in [(0.0, -1.0, 11.0), (50.0, -6.0, 6.0), (100.0, -11.0, 1.0), (150.0, -11.0, 1.0)] {
+            let mut channels = BTreeMap::new();
+            let mut trigger = trigger(TriggerMode::Normal, TriggerEdge::Rising, 10.0, pre_trigger);
+            push(&mut channels, 0, 1, |_| 0.0);
+            trigger.update(&channels, XAxisMode::Index, 1000);
+            push(&mut channels, 1, 100, |i| if i >= 50 { 1.0 } else { 0.0 });
+            trigger.update(&channels, XAxisMode::Index, 1000);
+            let points = &trigger.capture.as_ref().unwrap().traces[0].1;
+            assert_eq!((points[0][0], points[points.len() - 1][0]), (first, last), "{}", pre_trigger);
+        }
+    }
+
+    #[test]
+    fn detects_selected_edge_only() {
+        let mut channels = BTreeMap::new();
+        let mut rising = trigger(TriggerMode::Normal, TriggerEdge::Rising, 10.0, 50.0);
+        let mut falling = trigger(TriggerMode::Normal, TriggerEdge::Falling, 10.0, 50.0);
+        push(&mut channels, 0, 5, |_| 1.0);
+        rising.update(&channels, XAxisMode::Index, 1000);
+        falling.update(&channels, XAxisMode::Index, 1000);
+
+        // 从 1 降到电平正好 0.5 也算下降沿
+        push(&mut channels, 5, 40, |i| if i >= 30 { 0.5 } else { 1.0 });
+        rising.update(&channels, XAxisMode::Index, 1000);
+        falling.update(&channels, XAxisMode::Index, 1000);
+        assert!(rising.capture.is_none());
+        assert_eq!(rising.status_text(), "等待触发");
+        assert_eq!(trigger_x(&falling), Some(30.0));
+    }
+
+    #[test]
+    fn normal_mode_retriggers_after_window() {
+        let mut channels = BTreeMap::new();
+        let mut trigger = trigger(TriggerMode::Normal, TriggerEdge::Rising, 20.0, 25.0);
+        push(&mut channels, 0, 1, square);
+        trigger.update(&channels, XAxisMode::Index, 1000);
+        push(&mut channels, 1, 100, square);
+
+        // 每次触发后跳过窗口内的边沿，从窗口结束后继续查找
+        let mut fired = Vec::new();
+        for _ in 0..5 {
+            trigger.update(&channels, XAxisMode::Index, 1000);
+            fired.push(trigger_x(&trigger));
+        }
+        assert_eq!(fired, [5.0, 25.0, 45.0, 65.0, 65.0].map(Some));
+        assert_eq!(trigger.status_text(), "已触发, 采集中");
+
+        // 数据清空后从头开始检查
+        channels.get_mut(&0).unwrap().data.clear();
+        push(&mut channels, 0, 1, square);
+        trigger.update(&channels, XAxisMode::Index, 1000);
+        assert_eq!(trigger.status_text(), "已触发");
+    }
+
+    #[test]
+    fn single_mode_stops_until_rearmed() {
+        let mut channels = BTreeMap::new();
+        let mut trigger = trigger(TriggerMode::Single, TriggerEdge::Rising, 20.0, 25.0);
+        push(&mut channels, 0, 1, square);
+        trigger.update(&channels, XAxisMode::Index, 1000);
+        push(&mut channels, 1, 100, square);
+        trigger.update(&channels, XAxisMode::Index, 1000);
+        trigger.update(&channels, XAxisMode::Index, 1000);
+        assert_eq!(trigger_x(&trigger), Some(5.0));
+        assert_eq!(trigger.status_text(), "已触发(单次)");
+
+        trigger.rearm();
+        assert!(trigger.capture.is_none());
+        assert_eq!(trigger.status_text(), "等待触发");
+        trigger.update(&channels, XAxisMode::Index, 1000);
+        assert!(trigger.capture.is_none());
+        push(&mut channels, 100, 130, square);
+        trigger.update(&channels, XAxisMode::Index, 1000);
+        assert_eq!(trigger_x(&trigger), Some(105.0));
+    }
+
+    #[test]
+    fn auto_mode_free_runs_after_timeout() {
+        let mut channels = BTreeMap::new();
+        let mut auto = trigger(TriggerMode::Auto, TriggerEdge::Rising, 20.0, 25.0);
+        let mut normal = trigger(TriggerMode::Normal, TriggerEdge::Rising, 20.0, 25.0);
+        push(&mut channels, 0, 100, |_| 0.0);
+        auto.update(&channels, XAxisMode::Index, 1000);
+        normal.update(&channels, XAxisMode::Index, 1000);
+        assert!(auto.capture.is_none());
+
+        let timed_out = Instant::now() - AUTO_TIMEOUT * 2;
+        auto.last_fire = timed_out;
+        normal.last_fire = timed_out;
+        auto.update(&channels, XAxisMode::Index, 1000);
+        normal.update(&channels, XAxisMode::Index, 1000);
+        assert!(normal.capture.is_none());
+        // 最新的数据在窗口结束处
+        let capture = auto.capture.as_ref().unwrap();
+        assert!(capture.forced);
+        assert_eq!(capture.trigger_x, 84.0);
+        assert_eq!(auto.status_text(), "自动(未触发)");
+        assert!(auto.take_new_capture());
+        auto.update(&channels, XAxisMode::Index, 1000);
+        assert!(!auto.take_new_capture());
+
+        // 出现边沿后按正常触发显示
+        push(&mut channels, 100, 130, |i| if i >= 110 { 1.0 } else { 0.0 });
+        auto.update(&channels, XAxisMode::Index, 1000);
+        let capture = auto.capture.as_ref().unwrap();
+        assert!(!capture.forced);
+        assert_eq!(capture.trigger_x, 110.0);
+    }
+
+    #[test]
+    fn off_mode_does_nothing() {
+        let mut channels = BTreeMap::new();
+        let mut trigger = trigger(TriggerMode::Off, TriggerEdge::Rising, 20.0, 25.0);
+        push(&mut channels, 0, 100, square);
+        trigger.last_fire = Instant::now() - AUTO_TIMEOUT * 2;
+        trigger.update(&channels, XAxisMode::Index, 1000);
+        trigger.update(&channels, XAxisMode::Index, 1000);
+        assert!(trigger.capture.is_none());
+    }
+}
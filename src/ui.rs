@@ -1,13 +1,21 @@
 use crate::app::SerialAssistant;
+use crate::script::ScriptWidgetKind;
 use crate::utils;
+use crate::wave_ui;
+use crate::dashboard_ui;
+use crate::receive_view::{self, ReceiveDisplay};
+use crate::receive_buffer::{limit_label, RECEIVE_LIMITS};
 use eframe::egui;
-use egui::IconData;
 use std::{time::Duration};
-use egui_plot::{Line, Plot, PlotPoints, Legend};
 use std::io::Write;
 use rfd::FileDialog; 
 
 pub fn render_ui(app: &mut SerialAssistant, ctx: &egui::Context) {
+    // 脚本面板，显示脚本注册的控件
+    if !app.script.host.borrow().widgets.is_empty() {
+        render_script_panel(app, ctx);
+    }
+
     egui::CentralPanel::default().show(ctx, |ui| {  
         // 捕获鼠标位置
         if let Some(pointer_pos) = ui.input(|i| i.pointer.hover_pos()) {
@@ -59,6 +67,21 @@ pub fn render_ui(app: &mut SerialAssistant, ctx: &egui::Context) {
             app.update_status();
             app.update_transfer_rate();
             ui.label(&app.status_message);
+            if !app.script_status.is_empty() {
+                ui.separator();
+                ui.label(format!("脚本: {}", app.script_status));
+            }
+            if !app.alarm_status.is_empty() {
+                ui.separator();
+                ui.colored_label(egui::Color32::RED, format!("报警: {}", app.alarm_status));
+                if ui.small_button("×").on_hover_text("清除报警提示").clicked() {
+                    app.alarm_status.clear();
+                }
+            }
+            if !app.send_error.is_empty() {
+                ui.separator();
+                ui.colored_label(egui::Color32::RED, &app.send_error);
+            }
             ui.separator();
             egui::warn_if_debug_build(ui);
             ui.label(format!(
@@ -67,24 +90,35 @@ pub fn render_ui(app: &mut SerialAssistant, ctx: &egui::Context) {
                 app.frame_history.fps()
             ));
             ui.separator();
-            ui.label(format!(
-                "x:{}_y:{}",
-                app.pointer_pos.x,
-                app.pointer_pos.y
-            ));
+            // 鼠标在波形中时显示波形坐标，否则显示窗口坐标
+            match app.plot_hover {
+                Some([x, y]) => ui.label(format!(
+                    "波形 x: {} y: {:.4}",
+                    wave_ui::format_x(x, app.x_axis_mode, app.capture_epoch),
+                    y
+                )),
+                None => ui.label(format!(
+                    "x:{}_y:{}",
+                    app.pointer_pos.x,
+                    app.pointer_pos.y
+                )),
+            };
         });
     });
 
+    // 脚本窗口
+    if app.show_script {
+        render_script_window(app, ctx);
+    }
+
     // 波形显示窗口
     if app.plot_visible {
-        app.init_lua();//初始化脚本
-
         ctx.show_viewport_immediate (
             egui::ViewportId(egui::Id::new("serial_wave_window_id")),
             egui::ViewportBuilder::default()
                 .with_title("波形显示")
                 .with_inner_size([600.0, 400.0])
-                .with_icon(create_wave_icon()),
+                .with_icon(wave_ui::create_wave_icon()),
                 |ctx, class| {
                     if class == egui::ViewportClass::Embedded {
                         // Not a real viewport
@@ -94,15 +128,35 @@ pub fn render_ui(app: &mut SerialAssistant, ctx: &egui::Context) {
                                 ui.label("This egui integration does not support multiple viewports");
                             });
                     } else {
-                        egui::CentralPanel::default().show(ctx, |ui| {
-                            wave_viewport_content(app, ui, ctx);
-                        });
+                        wave_ui::render_wave_viewport(app, ctx);
                     }
                 }
         );
 
     }
 
+    // 仪表盘窗口
+    if app.dashboard.visible {
+        ctx.show_viewport_immediate(
+            egui::ViewportId(egui::Id::new("serial_dashboard_window_id")),
+            egui::ViewportBuilder::default()
+                .with_title("仪表盘")
+                .with_inner_size([640.0, 420.0])
+                .with_icon(wave_ui::create_wave_icon()),
+            |ctx, class| {
+                if class == egui::ViewportClass::Embedded {
+                    egui::Window::new("error information")
+                        .id(egui::Id::new("dashboard error information"))
+                        .show(ctx, |ui| {
+                            ui.label("This egui integration does not support multiple viewports");
+                        });
+                } else {
+                    dashboard_ui::render_dashboard_viewport(app, ctx);
+                }
+            },
+        );
+    }
+
     // 帮助窗口
     if app.show_help {
         let mut show = true;
@@ -116,7 +170,7 @@ pub fn render_ui(app: &mut SerialAssistant, ctx: &egui::Context) {
                 ui.heading("当前波形协议说明");
                 ui.add_space(8.0);
                 ui.label("1. 数据格式: ");
-                ui.label("   - 协议:AA + 数据长度(通道号 + 数据长度) + 通道号(0-9,大于9不绘制曲线)+ 4字节数据 + 4字节数据 + ...");
+                ui.label("   - 协议:AA + 数据长度(通道号 + 数据长度) + 通道号+ 4字节数据 + 4字节数据 + ...");
                 ui.label("   - 包含固定协议头AA,数据长度,通道号和数据值");
                 ui.label("   - 协议中FRAME_LENGT是整帧长度,是数据长度加2");
                 ui.label("   - 每个数据点的字节数BYTES_PER_POINT固定4字节");
@@ -127,14 +181,44 @@ pub fn render_ui(app: &mut SerialAssistant, ctx: &egui::Context) {
                 ui.label("   - (DATA_TYPE = float 通道1,数值:10.0, -10.0) : AA 09 03 00 00 20 41 00 00 20 C1  // 长度9 = 1(通道号) + 8(两组数据)");
                 ui.add_space(8.0);
                 ui.label("3. 通道说明: ");
-                ui.label("   - 支持多达 10 个通道");
-                ui.label("   - 每个通道独立显示不同颜色");
+                ui.label("   - 通道号不限, 收到数据时自动创建");
+                ui.label("   - 在波形窗口的通道列表中可以显示/隐藏、修改颜色和重命名, 设置保存在 config/channels.cfg");
                 ui.add_space(8.0);
                 ui.label("4. 显示控制: ");
                 ui.label("   - 支持缩放和拖动查看历史数据");
+                ui.label("   - 暂停后显示冻结, 数据仍在后台采集; 拖动波形下方的时间轴回看历史, 点击回到实时继续跟随");
+                ui.label("   - 光标: 拖动两条竖线和两条横线测量 Δx、1/Δx 和 Δy; 统计: 显示每个通道的最小、最大、平均、RMS、标准差、峰峰值和采样率");
+                ui.label("   - 直方图: 选中通道在可见窗口或全部历史中的数值分布, 显示平均值、标准差和对应的正态分布曲线");
+                ui.label("   - 频谱: 对选中通道最近 N 个点做 FFT, 可选矩形窗/汉宁窗/布莱克曼窗, 线性或 dB 幅度, 显示峰值频率");
+                ui.label("   - 派生通道: 用表达式(如 ch0 - ch1、sqrt(ch2^2 + ch3^2))或滑动平均、低通、微分、积分计算新通道, 定义保存在 config/derived.cfg");
+                ui.label("   - XY 图: 选择两个通道分别作为 X 和 Y, 按从最新点开始的顺序配对, 适合摇杆、磁力计校准和 I/Q 数据");
+                ui.label("   - 通道列表中可设置每个通道的增益和偏移(只影响显示); 分道显示时每个通道占一道并各自缩放, 悬停时显示原始数值");
+                ui.label("   - 报警: 为通道设置上限/下限, 越限时记录事件、在波形中标记并显示在状态栏, 可播放提示音、闪烁窗口、停止采集或发送命令; 启用报警后即使关闭波形窗口也会继续解析数据, 设置保存在 config/alarms.cfg");
+                ui.label("   - 通道位数大于 0 时按位显示为数字通道(类似逻辑分析仪), 可为每一位命名, 与模拟波形共用横轴");
+                ui.label("   - 横轴可选采样序号、相对时间(秒)或绝对时间(时:分:秒.毫秒), 时间取自串口读取线程收到数据的时刻");
+                ui.label("   - 触发: 选择触发源通道、上升沿/下降沿、电平和预触发比例, 满足条件时冻结一个窗口的波形");
+                ui.label("     自动: 超时未触发时显示最新波形; 常规: 只显示触发的波形; 单次: 触发一次后停止, 点击重新触发");
                 ui.label("   - 右键选中放大,左键双击还原,点击曲线图例显示和隐藏");
+                ui.label("   - 导出: 把全部或显示的通道导出为 CSV(同一帧的数据合并为一行, 每个通道附带横坐标列 x_chN), 或把当前波形保存为 PNG/SVG 图片");
+                ui.label("   - 导入: 打开导出的 CSV(保留横坐标, 也可以是普通数值表格)或接收日志, 日志中的接收数据按原始时间重新经过解析脚本, 可离线使用缩放、光标和统计");
+                ui.label("   - 参考波形: 快照当前视图或加载 CSV 作为参考, 半透明叠加在实时波形上, 可调整起点并显示与实时通道的差值");
+                ui.label("   - 仪表盘: 在接收区域勾选仪表盘打开, 点击编辑添加数值、表盘、柱状条、指示灯和迷你曲线控件并绑定通道, 布局保存在 config/dashboard.cfg");
                 ui.label("5. 自定义协议: ");
                 ui.label("   - 编辑waveform.lua文件以自定义波形协议,满足返回通道数和数据即可,数据可以是整型或浮点型");
+                ui.label("   - 返回 {channel = 1, points = {...}}: 一个通道多个数据点");
+                ui.label("   - 返回 {ch = 0, y = 1.5, x = 100}: 单个数据点, x 或设备时间戳 t 可选, 默认按采样序号");
+                ui.label("   - 各通道可附带 name = \"accel_x\", unit = \"g\" 作为通道名称和单位");
+                ui.label("   - 返回 {t = 12.5, {ch = 0, y = ..}, {ch = 1, y = ..}}: 一帧多个通道, 外层 x/t 作用于所有通道");
+                ui.label("   - 附加 fields = {temp = 25.0, mode = \"run\"}: 命名字段显示在波形窗口顶部");
+                ui.add_space(8.0);
+                ui.label("6. 脚本API: ");
+                ui.label("   - send(data) 发送字符串或字节数组, send_hex(\"AA 01\") 发送十六进制");
+                ui.label("   - on_receive(fn) 收到数据时调用 fn(bytes)");
+                ui.label("   - set_timer(ms, fn, repeat) 定时调用,返回编号; clear_timer(id) 取消");
+                ui.label("   - log(msg) 输出到脚本控制台, set_status(msg) 显示在状态栏");
+                ui.label("   - plot(ch, value) 添加波形点, plot_value(ch) 读取最新值, plot_clear(ch) 清空通道");
+                ui.label("   - ui_button(名称, fn), ui_slider(名称, 最小, 最大, fn, 初值), ui_checkbox(名称, fn, 初值)");
+                ui.label("   - ui_label(名称, 值) 显示数值, ui_set(名称, 值) 更新控件, ui_separator(), ui_clear()");
             });
         if !show {
             app.show_help = false;
@@ -275,12 +359,164 @@ fn render_top_controls(app: &mut SerialAssistant, ui: &mut egui::Ui, available_s
                     });
                 });
         }
+        if ui.button("脚本").clicked() {
+            app.show_script = !app.show_script;
+        }
         if ui.button("帮助").clicked() {
             app.show_help = true;  // 点击按钮时设置状态为 true
         }
     });
 }
 
+// 脚本面板：渲染脚本通过 ui_button、ui_slider 等注册的控件
+fn render_script_panel(app: &mut SerialAssistant, ctx: &egui::Context) {
+    let mut changed = Vec::new();
+
+    egui::SidePanel::right("script_panel")
+        .resizable(true)
+        .default_width(200.0)
+        .show(ctx, |ui| {
+            ui.heading("脚本面板");
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .id_salt("script_panel_scroll")
+                .show(ui, |ui| {
+                    let mut host = app.script.host.borrow_mut();
+                    for (index, widget) in host.widgets.iter_mut().enumerate() {
+                        match &mut widget.kind {
+                            ScriptWidgetKind::Button => {
+                                if ui.button(&widget.label).clicked() {
+                                    changed.push(index);
+                                }
+                            }
+                            ScriptWidgetKind::Slider { min, max, value } => {
+                                ui.label(&widget.label);
+                                if ui.add(egui::Slider::new(value, *min..=*max)).changed() {
+                                    changed.push(index);
+                                }
+                            }
+                            ScriptWidgetKind::Checkbox { checked } => {
+                                if ui.checkbox(checked, &widget.label).changed() {
+                                    changed.push(index);
+                                }
+                            }
+                            ScriptWidgetKind::Readout { value } => {
+                                ui.horizontal(|ui| {
+                                    ui.label(format!("{}:", widget.label));
+                                    ui.monospace(value.as_str());
+                                });
+                            }
+                            ScriptWidgetKind::Separator => {
+                                ui.separator();
+                            }
+                        }
+                    }
+                });
+        });
+
+    // 释放控件的借用后再调用脚本回调
+    for index in changed {
+        app.script.widget_changed(index);
+    }
+}
+
+// Lua脚本窗口：选择脚本、显示错误和脚本输出
+fn render_script_window(app: &mut SerialAssistant, ctx: &egui::Context) {
+    let mut show = true;
+    egui::Window::new("Lua脚本")
+        .id(egui::Id::new("lua_script_window"))
+        .default_size([480.0, 360.0])
+        .resizable(true)
+        .open(&mut show)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("脚本文件:");
+                ui.label(&app.script.path);
+            });
+            ui.horizontal(|ui| {
+                if ui.button("选择脚本").clicked()
+                    && let Some(path) = FileDialog::new()
+                        .add_filter("Lua脚本", &["lua"])
+                        .set_directory("config")
+                        .pick_file()
+                {
+                    app.script.set_path(&path.to_string_lossy());
+                }
+                if ui.button("重新加载").clicked() {
+                    app.script.load();
+                }
+                ui.checkbox(&mut app.script.auto_reload, "修改后自动重新加载");
+                ui.label(if app.script.lua.is_some() {
+                    "状态: 已加载"
+                } else if app.script.disabled {
+                    "状态: 已停用"
+                } else {
+                    "状态: 未加载"
+                });
+            });
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut app.script.time_budget_ms)
+                    .range(1..=5000)
+                    .prefix("单次执行限时: ")
+                    .suffix(" ms"));
+                ui.add(egui::DragValue::new(&mut app.script.memory_limit_mb)
+                    .range(1..=1024)
+                    .prefix("内存限制: ")
+                    .suffix(" MB"));
+                ui.label("(内存限制重新加载后生效)");
+            });
+
+            // 错误信息
+            let mut clear_error = false;
+            if let Some(error) = &app.script.error {
+                ui.separator();
+                ui.group(|ui| {
+                    ui.horizontal(|ui| {
+                        let title = match error.line {
+                            Some(line) => format!("脚本错误 (第 {} 行)", line),
+                            None => "脚本错误".to_string(),
+                        };
+                        ui.colored_label(egui::Color32::RED, title);
+                        if error.count > 1 {
+                            ui.label(format!("× {}", error.count));
+                        }
+                        if ui.small_button("忽略").clicked() {
+                            clear_error = true;
+                        }
+                    });
+                    if let (Some(line), Some(source_line)) = (error.line, &error.source_line) {
+                        ui.monospace(format!("{:>4} | {}", line, source_line));
+                    }
+                    ui.label(&error.message);
+                });
+            }
+            if clear_error {
+                app.script.clear_error();
+            }
+
+            // 控制台，显示脚本中 print 的输出
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("控制台");
+                if ui.small_button("清空").clicked() {
+                    app.script.console.borrow_mut().clear();
+                }
+            });
+            egui::ScrollArea::vertical()
+                .id_salt("lua_console_scroll")
+                .stick_to_bottom(true)
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    for line in app.script.console.borrow().iter() {
+                        ui.monospace(line);
+                    }
+                });
+        });
+    if !show {
+        app.show_script = false;
+    }
+}
+
 fn render_send_area(app: &mut SerialAssistant, ui: &mut egui::Ui, ctx: &egui::Context, available_width: f32, available_height: f32) {
     ui.group(|ui| {
         ui.set_max_width(available_width * 0.47);
@@ -347,16 +583,7 @@ fn render_send_area(app: &mut SerialAssistant, ui: &mut egui::Ui, ctx: &egui::Co
                         ui.ctx().request_repaint();
                     }
 
-                    let data = if app.is_hex_send {
-                        // 将输入按空格分割，解析每个十六进制数
-                        let hex_values: Vec<u8> = app.send_data
-                            .split_whitespace()
-                            .filter_map(|s| u8::from_str_radix(s, 16).ok())
-                            .collect();
-                        hex_values
-                    } else {
-                        app.send_data.as_bytes().to_vec()
-                    };
+                    let data = app.send_payload();
                 
                     if app.tcp_enabled {
                         // TCP 发送
@@ -408,12 +635,9 @@ fn render_receive_area(app: &mut SerialAssistant, ui: &mut egui::Ui, _ctx: &egui
             // 顶部控制区域
             ui.horizontal(|ui| {
                 ui.label("接收区域");
-                if ui.checkbox(&mut app.is_hex_display, "HEX显示").clicked() {
-                    if app.is_hex_display {
-                        app.received_data = utils::ascii_to_hex(&app.received_data);
-                    } else {
-                        app.received_data = utils::hex_to_ascii(&app.received_data);
-                    }
+                // 显示方式只影响绘制，缓冲区中始终是原始字节
+                for display in ReceiveDisplay::ALL {
+                    ui.selectable_value(&mut app.receive_display, display, display.label());
                 }
 
                 if ui.checkbox(&mut app.plot_visible, "波形显示").clicked() {
@@ -421,6 +645,9 @@ fn render_receive_area(app: &mut SerialAssistant, ui: &mut egui::Ui, _ctx: &egui
                         app.packet_buffer.clear();
                     } 
                 }
+                if ui.checkbox(&mut app.dashboard.visible, "仪表盘").clicked() && app.dashboard.visible && !app.plot_visible {
+                    app.packet_buffer.clear();
+                }
 
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                     ui.label(format!("已接收: {} 字节", app.bytes_received));
@@ -428,26 +655,30 @@ fn render_receive_area(app: &mut SerialAssistant, ui: &mut egui::Ui, _ctx: &egui
             });
             
             // 接收数据显示区域
-            egui::ScrollArea::vertical()
-                .id_salt("receive_area_scroll")  // 为接收区域添加唯一的ID
-                .max_height(available_height - 100.0)
-                .stick_to_bottom(true)
-                .show(ui, |ui| {
-                    ui.add_sized(
-                        [ui.available_width(), ui.available_height()],
-                        egui::TextEdit::multiline(&mut app.received_data)
-                            .desired_width(f32::INFINITY)
-                            .desired_rows(15)
-                            .lock_focus(true)
-                    );
-                });
+            receive_view::render_receive_view(app, ui, available_height - 100.0);
             
             // 底部按钮区域
             ui.horizontal(|ui| {
                 if ui.button("清空接收").clicked() {
-                    app.received_data.clear();
+                    app.received.clear();
                     app.bytes_received = 0;
                 }
+                if ui.button("复制").on_hover_text("复制缓冲区中的全部接收数据").clicked() {
+                    ui.ctx().copy_text(receive_view::buffer_text(&app.received, app.receive_display));
+                }
+                ui.checkbox(&mut app.auto_scroll, "自动滚动");
+
+                let mut limit = app.received.max_bytes;
+                egui::ComboBox::from_id_salt("receive_limit")
+                    .selected_text(format!("缓冲区 {}", limit_label(limit)))
+                    .show_ui(ui, |ui| {
+                        for preset in RECEIVE_LIMITS {
+                            ui.selectable_value(&mut limit, preset, limit_label(preset));
+                        }
+                    });
+                if limit != app.received.max_bytes {
+                    app.received.set_max_bytes(limit);
+                }
                 
                 // 处理日志记录复选框
                 let log_enabled_before = app.log_enabled;
@@ -477,125 +708,3 @@ fn render_receive_area(app: &mut SerialAssistant, ui: &mut egui::Ui, _ctx: &egui
         });
     });
 }
-
-fn wave_viewport_content(app: &mut SerialAssistant, ui: &mut egui::Ui, _ctx: &egui::Context) {    
-    let available_size = ui.available_size();
-    // 确保高度和宽度不为负值
-    let height = available_size.y.max(200.0);
-    let width = available_size.x.max(300.0);
-
-        // 计算所有通道的数据范围
-    let mut min_y = f64::MAX;
-    let mut max_y = f64::MIN;
-
-    for plot_data in &app.plot_data_per_channel {
-        if !plot_data.is_empty() {
-            let (_, local_min) = plot_data.iter()
-            .map(|(_, y)| *y)
-            .fold((0.0, f64::INFINITY), |acc, y| (y, acc.1.min(y)));
-            let (_, local_max) = plot_data.iter()
-            .map(|(_, y)| *y)
-            .fold((0.0, f64::NEG_INFINITY), |acc, y| (y, acc.1.max(y)));
-
-            min_y = min_y.min(local_min);
-            max_y = max_y.max(local_max);
-        }
-    }
-
-    // 设置默认范围并处理边界情况
-    if min_y == f64::MAX || max_y == f64::MIN {
-        min_y = -1.0;
-        max_y = 1.0;
-    }
-
-    // 添加边距，使显示更美观
-    let range = if max_y > min_y { (max_y - min_y).max(1e-6) } else { 1.0 };
-    let margin = range * 0.1;
-    let y_min = min_y - margin;
-    let y_max = max_y + margin;
-
-    // 绘制波形
-    Plot::new("serial_wave_plot")
-    .id(egui::Id::new("serial_wave_plot_area"))
-    .view_aspect(2.0)
-    .include_y(y_min) 
-    .include_y(y_max) 
-    .show_axes([true, true])
-    .show_grid([true, true])
-    .legend(Legend::default())
-    .allow_zoom(true)
-    .allow_drag(true)
-    .height(height)
-    .width(width-20.0)
-    .show(ui, |plot_ui| {
-        for (channel, plot_data) in app.plot_data_per_channel.iter().enumerate() {
-            if !plot_data.is_empty() {
-                let points: Vec<[f64; 2]> = plot_data
-                    .iter()
-                    .map(|(x, y)| [*x, *y])
-                    .collect();
-                
-                // 根据通道号选择不同的颜色
-                let color = match channel {
-                    0 => egui::Color32::from_rgb(255, 0, 0),   // 红色
-                    1 => egui::Color32::from_rgb(0, 255, 0),   // 绿色
-                    2 => egui::Color32::from_rgb(0, 0, 255),   // 蓝色
-                    3 => egui::Color32::from_rgb(255, 255, 0), // 黄色
-                    4 => egui::Color32::from_rgb(255, 0, 255), // 紫色
-                    5 => egui::Color32::from_rgb(0, 255, 255), // 青色
-                    6 => egui::Color32::from_rgb(128, 0, 128), // 深紫色
-                    7 => egui::Color32::from_rgb(128, 128, 0), // 橄榄色
-                    8 => egui::Color32::from_rgb(0, 128, 128), // 深青色
-                    9 => egui::Color32::from_rgb(128, 128, 128), // 灰色
-                    _ => egui::Color32::from_rgb(100, 200, 100), // 默认颜色
-                };
-                
-                let line = Line::new(PlotPoints::from_iter(points))
-                    .color(color)
-                    .name(format!("通道 {}", channel))
-                    .width(2.0);
-                plot_ui.line(line);
-            }
-        }
-    });
-
-    // 处理关闭请求
-    if ui.input(|i| i.viewport().close_requested()) {
-        app.plot_visible = false;
-    }
-
-     // 捕获鼠标位置
-     if let Some(pointer_pos) = ui.input(|i| i.pointer.hover_pos()) {
-        app.pointer_pos = pointer_pos;
-    }
-}
-
-
-// 添加创建图标的函数
-fn create_wave_icon() -> IconData {
-    let width = 64;
-    let height = 64;
-    let mut rgba = Vec::with_capacity(width * height * 4);
-    
-    // 创建一个正弦波形图标
-    for y in 0..height {
-        for x in 0..width {//波形周期从 4π
-            let wave = ((x as f32 / width as f32 * 4.0 * std::f32::consts::PI).sin() * 0.5 + 0.5) * height as f32;
-            let is_wave = (y as f32 - wave).abs() < 5.0;//线条宽度从 5.0
-            
-            //纯蓝色波形（RGB: 0,0,255）
-            rgba.extend_from_slice(&[
-                if is_wave { 0 } else { 255 },    // R
-                if is_wave { 0 } else { 255 },    // G
-                if is_wave { 255 } else { 255 },  // B
-                255,                              // A
-            ]);
-        }
-    }
-    
-    IconData {
-        rgba,
-        width: width as _,
-        height: height as _,
-    }
-}
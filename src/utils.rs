@@ -1,15 +1,17 @@
-// 将十六进制字符串转换为字节数组
-pub fn hex_to_bytes(hex_str: &str) -> Vec<u8> {
-    hex_str
-    .split_whitespace()  // 按空格分割
-    .filter_map(|s| {    // 过滤并转换
-        if let Ok(byte) = u8::from_str_radix(s, 16) {
-            Some(byte)
-        } else {
-            None
+// 将十六进制字符串转换为字节数组，字节之间可以用空白分隔，也可以连续书写，如 "AA 01 02" 或 "AA0102"
+// 单独的一位数字表示一个字节，其他奇数长度或非十六进制字符返回错误
+pub fn hex_to_bytes(hex_str: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    for token in hex_str.split_whitespace() {
+        if !token.chars().all(|c| c.is_ascii_hexdigit()) || (token.len() > 1 && !token.len().is_multiple_of(2)) {
+            return Err(format!("无效的十六进制数: {}", token));
         }
-    })
-    .collect()
+        for pair in token.as_bytes().chunks(2) {
+            let digits = std::str::from_utf8(pair).map_err(|e| e.to_string())?;
+            bytes.push(u8::from_str_radix(digits, 16).map_err(|e| e.to_string())?);
+        }
+    }
+    Ok(bytes)
 }
 
 // 修改 bytes_to_hex 函数，使其格式与 hex_to_bytes 兼容
@@ -51,4 +53,126 @@ pub fn hex_to_ascii(hex_string: &str) -> String {
     }
     println!("{}",ascii_string.len());
     ascii_string
-}
\ No newline at end of file
+}
+// 转换为 C 语言数组定义，每行 16 个字节
+pub fn bytes_to_c_array(bytes: &[u8]) -> String {
+    let rows: Vec<String> = bytes
+        .chunks(16)
+        .map(|row| row.iter().map(|b| format!("0x{:02X}", b)).collect::<Vec<_>>().join(", "))
+        .collect();
+    if rows.is_empty() {
+        return "unsigned char data[0] = {\n};".to_string();
+    }
+    format!("unsigned char data[{}] = {{\n    {}\n}};", bytes.len(), rows.join(",\n    "))
+}
+
+pub fn bytes_to_base64(bytes: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = (chunk[0] as u32) << 16 | (*chunk.get(1).unwrap_or(&0) as u32) << 8 | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(TABLE[(group >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+// 播放系统提示音。程序作为窗口程序运行时没有控制台，不能只向终端输出响铃字符
+pub fn system_beep() {
+    #[cfg(windows)]
+    {
+        #[link(name = "user32")]
+        unsafe extern "system" {
+            fn MessageBeep(kind: u32) -> i32;
+        }
+        // MB_ICONEXCLAMATION 对应系统的"感叹号"提示音
+        unsafe {
+            MessageBeep(0x30);
+        }
+    }
+    #[cfg(target_os = "macos")]
+    {
+        #[link(name = "AppKit", kind = "framework")]
+        unsafe extern "C" {
+            fn NSBeep();
+        }
+        unsafe {
+            NSBeep();
+        }
+    }
+    #[cfg(not(any(windows, target_os = "macos")))]
+    {
+        // 桌面环境一般带有 libcanberra，没有时退回终端响铃
+        // 播放进程在后台线程中等待结束，避免每次提示音留下僵尸进程
+        let played = std::process::Command::new("canberra-gtk-play")
+            .args(["--id", "bell"])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .map(|mut child| std::thread::spawn(move || child.wait()))
+            .is_ok();
+        if !played {
+            use std::io::Write;
+            print!("\x07");
+            let _ = std::io::stdout().flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_to_bytes_accepts_spaced_and_contiguous_pairs() {
+        assert_eq!(hex_to_bytes("01 ab FF"), Ok(vec![0x01, 0xAB, 0xFF]));
+        assert_eq!(hex_to_bytes("01abFF\n10\t2"), Ok(vec![0x01, 0xAB, 0xFF, 0x10, 0x02]));
+        assert_eq!(hex_to_bytes("  "), Ok(Vec::new()));
+        assert_eq!(hex_to_bytes(&bytes_to_hex(&[0, 0x7F, 0x80, 0xFF])), Ok(vec![0, 0x7F, 0x80, 0xFF]));
+    }
+
+    #[test]
+    fn hex_to_bytes_rejects_odd_digits_and_invalid_characters() {
+        assert!(hex_to_bytes("ABC").unwrap_err().contains("ABC"));
+        assert!(hex_to_bytes("01 0G").unwrap_err().contains("0G"));
+        assert!(hex_to_bytes("0x12").is_err());
+        assert!(hex_to_bytes("12,34").is_err());
+        assert!(hex_to_bytes("１２").is_err());
+        assert!(hex_to_bytes("-1").is_err());
+    }
+
+    #[test]
+    fn base64_pads_partial_groups() {
+        assert_eq!(bytes_to_base64(b""), "");
+        assert_eq!(bytes_to_base64(b"M"), "TQ==");
+        assert_eq!(bytes_to_base64(b"Ma"), "TWE=");
+        assert_eq!(bytes_to_base64(b"Man"), "TWFu");
+        assert_eq!(bytes_to_base64(b"Many"), "TWFueQ==");
+        assert_eq!(bytes_to_base64(&[0xFF, 0xFE, 0xFD]), "//79");
+        assert_eq!(bytes_to_base64(&[0x00]), "AA==");
+    }
+
+    #[test]
+    fn c_array_wraps_every_16_bytes() {
+        assert_eq!(bytes_to_c_array(&[]), "unsigned char data[0] = {\n};");
+        assert_eq!(bytes_to_c_array(&[0x01]), "unsigned char data[1] = {\n    0x01\n};");
+        assert_eq!(bytes_to_c_array(&[0x01, 0xAB]), "unsigned char data[2] = {\n    0x01, 0xAB\n};");
+        assert_eq!(bytes_to_c_array(&[0, 1, 0xFF]), "unsigned char data[3] = {\n    0x00, 0x01, 0xFF\n};");
+
+        let bytes: Vec<u8> = (0..17).collect();
+        let text = bytes_to_c_array(&bytes);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "unsigned char data[17] = {");
+        assert!(lines[1].starts_with("    0x00, ") && lines[1].ends_with("0x0F,"));
+        assert_eq!(lines[1].matches("0x").count(), 16);
+        assert_eq!(lines[2], "    0x10");
+        assert_eq!(lines[3], "};");
+        assert_eq!(bytes_to_c_array(&[0; 16]).lines().count(), 3);
+    }
+}
@@ -0,0 +1,1499 @@
+use crate::app::SerialAssistant;
+use crate::channel::{PlotChannel, MAX_BIT_COUNT};
+use crate::sample_buffer::{HistoryLimit, XAxisMode};
+use crate::trigger::{TriggerEdge, TriggerMode};
+use crate::fft::{FftWindow, FFT_SIZES};
+use crate::derived::{DerivedChannel, DerivedKind};
+use crate::xy::PlotMode;
+use crate::alarm::{AlarmAction, AlarmLevel, ChannelAlarm};
+use crate::reference::Reference;
+use chrono::{DateTime, Local};
+use eframe::egui;
+use egui::IconData;
+use egui_plot::{Bar, BarChart, HLine, Legend, Line, LineStyle, Plot, PlotBounds, PlotPoint, PlotPoints, PlotUi, Points, Text, VLine};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::btree_map::Entry;
+use std::rc::Rc;
+
+// 波形窗口：顶部工具栏，左侧通道列表，中间波形
+pub fn render_wave_viewport(app: &mut SerialAssistant, ctx: &egui::Context) {
+    save_requested_screenshot(app, ctx);
+
+    egui::TopBottomPanel::top("wave_toolbar").show(ctx, |ui| {
+        ui.horizontal_wrapped(|ui| {
+            ui.toggle_value(&mut app.show_channel_panel, "通道列表");
+            ui.separator();
+
+            // 暂停只冻结显示，数据仍在后台写入历史
+            if app.wave_paused {
+                if ui.button("回到实时").clicked() {
+                    app.resume_live();
+                }
+            } else if ui.button("暂停").clicked() {
+                app.wave_paused = true;
+            }
+            ui.separator();
+
+            let mut history_limit = app.history_limit;
+            egui::ComboBox::from_label("历史长度")
+                .selected_text(history_limit.label())
+                .show_ui(ui, |ui| {
+                    for preset in HistoryLimit::PRESETS {
+                        ui.selectable_value(&mut history_limit, preset, preset.label());
+                    }
+                });
+            if history_limit != app.history_limit {
+                app.set_history_limit(history_limit);
+            }
+            ui.separator();
+
+            let x_axis_mode = app.x_axis_mode;
+            egui::ComboBox::from_label("横轴")
+                .selected_text(app.x_axis_mode.label())
+                .show_ui(ui, |ui| {
+                    for mode in [XAxisMode::Index, XAxisMode::Relative, XAxisMode::Absolute] {
+                        ui.selectable_value(&mut app.x_axis_mode, mode, mode.label());
+                    }
+                });
+            if app.x_axis_mode != x_axis_mode {
+                app.trigger.rearm();
+            }
+            ui.separator();
+
+            ui.toggle_value(&mut app.cursors.enabled, "光标");
+            if app.cursors.enabled && ui.small_button("重置光标").clicked() {
+                app.cursors.reset();
+            }
+            if ui.toggle_value(&mut app.statistics.show, "统计").changed() {
+                app.statistics.invalidate();
+            }
+            if ui.toggle_value(&mut app.spectrum.show, "频谱").changed() {
+                app.spectrum.invalidate();
+            }
+            if ui.toggle_value(&mut app.histogram.show, "直方图").changed() {
+                app.histogram.invalidate();
+            }
+            ui.toggle_value(&mut app.show_derived, "派生通道");
+            ui.toggle_value(&mut app.stacked_lanes, "分道显示");
+            let alarm_label = if app.alarms.events.is_empty() { "报警".to_string() } else { format!("报警 ({})", app.alarms.events.len()) };
+            ui.toggle_value(&mut app.alarms.show, alarm_label);
+            ui.toggle_value(&mut app.references.show, "参考波形");
+            ui.menu_button("导出", |ui| export_menu(app, ui));
+            if ui.button("导入").on_hover_text("导入 CSV 或接收日志, 替换当前波形数据").clicked()
+                && let Some(path) = rfd::FileDialog::new()
+                    .add_filter("CSV", &["csv"])
+                    .add_filter("接收日志", &["txt", "log"])
+                    .pick_file()
+            {
+                app.file_message = Some(app.import_file(&path).unwrap_or_else(|e| format!("导入失败: {}", e)));
+            }
+            if let Some(message) = &app.file_message {
+                ui.label(message);
+            }
+            ui.separator();
+
+            egui::ComboBox::from_label("显示")
+                .selected_text(app.plot_mode.label())
+                .show_ui(ui, |ui| {
+                    for mode in [PlotMode::TimeSeries, PlotMode::Xy] {
+                        ui.selectable_value(&mut app.plot_mode, mode, mode.label());
+                    }
+                });
+            if app.plot_mode == PlotMode::Xy {
+                xy_controls(app, ui);
+            } else {
+                trigger_controls(app, ui);
+            }
+        });
+    });
+
+    if app.show_channel_panel {
+        egui::SidePanel::left("wave_channel_panel")
+            .resizable(true)
+            .default_width(180.0)
+            .show(ctx, |ui| {
+                render_channel_panel(app, ui);
+            });
+    }
+
+    if app.show_derived {
+        let mut open = true;
+        egui::Window::new("派生通道")
+            .open(&mut open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                render_derived_editor(app, ui);
+            });
+        app.show_derived &= open;
+    }
+
+    if app.alarms.show {
+        let mut open = true;
+        egui::Window::new("报警")
+            .open(&mut open)
+            .default_width(460.0)
+            .show(ctx, |ui| {
+                render_alarm_window(app, ui);
+            });
+        app.alarms.show &= open;
+    }
+
+    if app.histogram.show {
+        let mut open = true;
+        egui::Window::new("直方图")
+            .open(&mut open)
+            .default_size([420.0, 320.0])
+            .show(ctx, |ui| {
+                render_histogram(app, ui);
+            });
+        app.histogram.show &= open;
+    }
+
+    if app.references.show {
+        let mut open = true;
+        egui::Window::new("参考波形")
+            .open(&mut open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                render_reference_window(app, ui);
+            });
+        app.references.show &= open;
+    }
+
+    if app.spectrum.show {
+        egui::SidePanel::right("wave_spectrum_panel")
+            .resizable(true)
+            .default_width(400.0)
+            .show(ctx, |ui| {
+                render_spectrum(app, ui);
+            });
+    }
+
+    if app.statistics.show {
+        egui::TopBottomPanel::bottom("wave_stats_panel")
+            .resizable(true)
+            .show(ctx, |ui| {
+                render_statistics(app, ui);
+            });
+    }
+
+    egui::CentralPanel::default().show(ctx, |ui| {
+        wave_viewport_content(app, ui, ctx);
+    });
+}
+
+// 导出菜单：CSV 数据和 PNG/SVG 图片
+fn export_menu(app: &mut SerialAssistant, ui: &mut egui::Ui) {
+    ui.checkbox(&mut app.export_visible_only, "只导出当前视图范围");
+    let window = app.wave_visible_x.filter(|_| app.export_visible_only);
+
+    for (label, visible_only) in [("导出全部通道 CSV", false), ("导出显示通道 CSV", true)] {
+        if ui.button(label).clicked() {
+            ui.close_menu();
+            let Some(path) = rfd::FileDialog::new().add_filter("CSV", &["csv"]).set_file_name("waveform.csv").save_file() else {
+                continue;
+            };
+            let channels: Vec<_> = app
+                .plot_data_per_channel
+                .iter()
+                .filter(|(_, c)| c.visible || !visible_only)
+                .map(|(index, c)| (*index, c))
+                .collect();
+            app.file_message = Some(match crate::export::export_csv(&path, &channels, window, app.x_axis_mode, app.capture_epoch) {
+                Ok(rows) => format!("已导出 {} 行到 {}", rows, path.display()),
+                Err(e) => format!("导出 CSV 失败: {}", e),
+            });
+        }
+    }
+    ui.separator();
+
+    if ui.button("保存图片 PNG").clicked() {
+        ui.close_menu();
+        if let Some(path) = rfd::FileDialog::new().add_filter("PNG", &["png"]).set_file_name("waveform.png").save_file() {
+            // 截图在下一帧以事件形式返回
+            app.wave_export_png = Some(path);
+            ui.ctx().send_viewport_cmd(egui::ViewportCommand::Screenshot(Default::default()));
+        }
+    }
+    let can_svg = app.plot_mode == PlotMode::TimeSeries && app.wave_visible_x.is_some();
+    if ui.add_enabled(can_svg, egui::Button::new("保存图片 SVG")).clicked() {
+        ui.close_menu();
+        if let (Some(window), Some(path)) = (
+            app.wave_visible_x,
+            rfd::FileDialog::new().add_filter("SVG", &["svg"]).set_file_name("waveform.svg").save_file(),
+        ) {
+            let channels: Vec<_> = app.plot_data_per_channel.values().filter(|c| c.visible && !c.is_logic()).collect();
+            app.file_message = Some(match crate::export::export_svg(&path, &channels, window, app.x_axis_mode, app.capture_epoch) {
+                Ok(()) => format!("已保存 {}", path.display()),
+                Err(e) => format!("保存 SVG 失败: {}", e),
+            });
+        }
+    }
+}
+
+fn save_requested_screenshot(app: &mut SerialAssistant, ctx: &egui::Context) {
+    if app.wave_export_png.is_none() {
+        return;
+    }
+    // 截图还没有返回时保留路径，等待下一帧
+    let Some(image) = ctx.input(|i| {
+        i.raw.events.iter().find_map(|event| match event {
+            egui::Event::Screenshot { image, .. } => Some(image.clone()),
+            _ => None,
+        })
+    }) else {
+        return;
+    };
+    if let (Some(path), Some(rect)) = (app.wave_export_png.take(), app.wave_plot_rect) {
+        app.file_message = Some(match crate::export::save_png(&path, &image, rect, ctx.pixels_per_point()) {
+            Ok(()) => format!("已保存 {}", path.display()),
+            Err(e) => format!("保存 PNG 失败: {}", e),
+        });
+    }
+}
+
+// 派生通道窗口：已定义的派生通道列表，以及添加或修改派生通道的表单
+fn render_derived_editor(app: &mut SerialAssistant, ui: &mut egui::Ui) {
+    let mut to_remove = None;
+    let mut to_edit = None;
+
+    if let Some(error) = &app.derived.error {
+        ui.colored_label(egui::Color32::RED, error);
+    }
+    if app.derived.channels.is_empty() {
+        ui.label("暂无派生通道");
+    } else {
+        egui::Grid::new("derived_channel_grid").striped(true).show(ui, |ui| {
+            for title in ["通道", "名称", "定义", ""] {
+                ui.strong(title);
+            }
+            ui.end_row();
+
+            for channel in &app.derived.channels {
+                ui.label(format!("{}", channel.index));
+                ui.label(&channel.name);
+                ui.monospace(format!("{}: {}", channel.kind.label(), channel.kind.describe()));
+                ui.horizontal(|ui| {
+                    if ui.small_button("编辑").clicked() {
+                        to_edit = Some(channel.index);
+                    }
+                    if ui.small_button("删除").clicked() {
+                        to_remove = Some(channel.index);
+                    }
+                });
+                ui.end_row();
+
+                if let Some(error) = &channel.error {
+                    ui.label("");
+                    ui.colored_label(egui::Color32::RED, error);
+                    ui.end_row();
+                }
+            }
+        });
+    }
+
+    if let Some(index) = to_edit
+        && let Some(channel) = app.derived.channels.iter().find(|c| c.index == index)
+    {
+        app.derived_editor.index = channel.index;
+        app.derived_editor.name = channel.name.clone();
+        app.derived_editor.kind = channel.kind.clone();
+    }
+    if let Some(index) = to_remove {
+        app.derived.remove(index);
+        app.plot_data_per_channel.remove(&index);
+        app.save_derived_settings();
+        app.save_channel_settings();
+    }
+
+    ui.separator();
+    let editor = &mut app.derived_editor;
+    ui.horizontal(|ui| {
+        ui.label("输出通道:");
+        ui.add(egui::DragValue::new(&mut editor.index));
+        ui.label("名称:");
+        ui.add(egui::TextEdit::singleline(&mut editor.name).hint_text("留空使用定义").desired_width(120.0));
+    });
+
+    // 切换类型时保留已选的输入通道
+    let source = match &editor.kind {
+        DerivedKind::Expression(_) => 0,
+        DerivedKind::MovingAverage { source, .. }
+        | DerivedKind::LowPass { source, .. }
+        | DerivedKind::Derivative { source }
+        | DerivedKind::Integral { source } => *source,
+    };
+    let choices = [
+        DerivedKind::Expression("ch0 - ch1".to_string()),
+        DerivedKind::MovingAverage { source, window: 10 },
+        DerivedKind::LowPass { source, alpha: 0.1 },
+        DerivedKind::Derivative { source },
+        DerivedKind::Integral { source },
+    ];
+    egui::ComboBox::from_label("类型")
+        .selected_text(editor.kind.label())
+        .show_ui(ui, |ui| {
+            for choice in choices {
+                let selected = std::mem::discriminant(&choice) == std::mem::discriminant(&editor.kind);
+                if ui.selectable_label(selected, choice.label()).clicked() && !selected {
+                    editor.kind = choice;
+                }
+            }
+        });
+
+    ui.horizontal(|ui| match &mut editor.kind {
+        DerivedKind::Expression(expression) => {
+            ui.label("表达式:");
+            ui.add(egui::TextEdit::singleline(expression).code_editor().desired_width(300.0));
+        }
+        DerivedKind::MovingAverage { source, window } => {
+            ui.label("输入通道:");
+            ui.add(egui::DragValue::new(source));
+            ui.label("点数:");
+            ui.add(egui::DragValue::new(window).range(1..=100_000));
+        }
+        DerivedKind::LowPass { source, alpha } => {
+            ui.label("输入通道:");
+            ui.add(egui::DragValue::new(source));
+            ui.label("平滑系数:");
+            ui.add(egui::Slider::new(alpha, 0.001..=1.0).logarithmic(true));
+        }
+        DerivedKind::Derivative { source } | DerivedKind::Integral { source } => {
+            ui.label("输入通道:");
+            ui.add(egui::DragValue::new(source));
+        }
+    });
+    ui.label("表达式中 ch0、ch1 等表示通道的值, 每个输入数据点计算一次, X 与输入数据点相同, 可使用 sqrt、abs、sin、cos、exp、log、max、min、pi 等数学函数, ^ 表示乘方");
+
+    if ui.button("添加/更新").clicked() {
+        let name = if editor.name.trim().is_empty() { editor.kind.describe() } else { editor.name.trim().to_string() };
+        let index = editor.index;
+        match app.derived.add(DerivedChannel::new(index, name, editor.kind.clone())) {
+            Ok(()) => {
+                editor.error = None;
+                // 定义改变后旧数据不再有意义
+                if let Some(channel) = app.plot_data_per_channel.get_mut(&index) {
+                    channel.clear();
+                }
+                app.apply_derived_names();
+                app.save_derived_settings();
+            }
+            Err(e) => editor.error = Some(e),
+        }
+    }
+    if let Some(error) = &app.derived_editor.error {
+        ui.colored_label(egui::Color32::RED, error);
+    }
+}
+
+// 频谱面板：通道、点数、窗函数、幅度刻度和采样率设置，下方为频谱图
+fn render_spectrum(app: &mut SerialAssistant, ui: &mut egui::Ui) {
+    ui.heading("频谱");
+    let spectrum = &mut app.spectrum;
+    let before = (spectrum.channel, spectrum.size, spectrum.window, spectrum.db, spectrum.auto_rate, spectrum.sample_rate);
+
+    ui.horizontal_wrapped(|ui| {
+        let channel_name = app
+            .plot_data_per_channel
+            .get(&spectrum.channel)
+            .map_or_else(|| format!("通道 {}", spectrum.channel), |c| c.display_name().to_string());
+        egui::ComboBox::from_label("通道")
+            .selected_text(channel_name)
+            .show_ui(ui, |ui| {
+                for (index, channel) in &app.plot_data_per_channel {
+                    ui.selectable_value(&mut spectrum.channel, *index, channel.display_name());
+                }
+            });
+
+        egui::ComboBox::from_label("点数")
+            .selected_text(format!("{}", spectrum.size))
+            .show_ui(ui, |ui| {
+                for size in FFT_SIZES {
+                    ui.selectable_value(&mut spectrum.size, size, format!("{}", size));
+                }
+            });
+
+        egui::ComboBox::from_label("窗函数")
+            .selected_text(spectrum.window.label())
+            .show_ui(ui, |ui| {
+                for window in FftWindow::ALL {
+                    ui.selectable_value(&mut spectrum.window, window, window.label());
+                }
+            });
+    });
+
+    ui.horizontal_wrapped(|ui| {
+        ui.radio_value(&mut spectrum.db, false, "线性");
+        ui.radio_value(&mut spectrum.db, true, "dB");
+        ui.separator();
+        ui.checkbox(&mut spectrum.auto_rate, "按到达时间估算采样率");
+        if !spectrum.auto_rate {
+            ui.add(egui::DragValue::new(&mut spectrum.sample_rate).range(1e-3..=1e9).suffix(" Hz"));
+        }
+    });
+
+    if (spectrum.channel, spectrum.size, spectrum.window, spectrum.db, spectrum.auto_rate, spectrum.sample_rate) != before {
+        spectrum.invalidate();
+    }
+    spectrum.update(&app.plot_data_per_channel);
+
+    let Some(result) = &spectrum.result else {
+        ui.label("数据不足或无法估算采样率");
+        return;
+    };
+    ui.horizontal_wrapped(|ui| {
+        ui.monospace(format!("采样率: {:.2} Hz", result.sample_rate));
+        ui.separator();
+        ui.monospace(format!("点数: {}", result.size));
+        ui.separator();
+        ui.monospace(format!("分辨率: {:.4} Hz", result.sample_rate / result.size as f64));
+        if let Some([frequency, magnitude]) = result.peak {
+            ui.separator();
+            ui.monospace(format!("峰值: {:.4} Hz, {:.4}{}", frequency, magnitude, if spectrum.db { " dB" } else { "" }));
+        }
+    });
+
+    let color = app.plot_data_per_channel.get(&spectrum.channel).map_or(egui::Color32::GRAY, |c| c.color);
+    Plot::new("wave_spectrum_plot")
+        .x_axis_label("Hz")
+        .label_formatter(|_name, value| format!("{:.4} Hz\n{:.4}", value.x, value.y))
+        .show(ui, |plot_ui| {
+            plot_ui.line(Line::new(PlotPoints::new(result.points.clone())).color(color));
+            if let Some(peak) = result.peak {
+                plot_ui.points(Points::new(vec![peak]).radius(4.0).color(egui::Color32::RED));
+            }
+        });
+}
+
+// 直方图：选中通道在可见窗口或全部历史中的数值分布，叠加同均值和标准差的正态分布曲线
+fn render_histogram(app: &mut SerialAssistant, ui: &mut egui::Ui) {
+    let histogram = &mut app.histogram;
+    let before = (histogram.channel, histogram.bins, histogram.full_history);
+
+    ui.horizontal_wrapped(|ui| {
+        let channel_name = app
+            .plot_data_per_channel
+            .get(&histogram.channel)
+            .map_or_else(|| format!("通道 {}", histogram.channel), |c| c.display_name().to_string());
+        egui::ComboBox::from_id_salt("histogram_channel")
+            .selected_text(channel_name)
+            .show_ui(ui, |ui| {
+                for (index, channel) in &app.plot_data_per_channel {
+                    ui.selectable_value(&mut histogram.channel, *index, channel.display_name());
+                }
+            });
+        ui.label("区间数:");
+        ui.add(egui::DragValue::new(&mut histogram.bins).range(1..=1000));
+        ui.radio_value(&mut histogram.full_history, false, "可见窗口");
+        ui.radio_value(&mut histogram.full_history, true, "全部历史");
+    });
+
+    if (histogram.channel, histogram.bins, histogram.full_history) != before {
+        histogram.invalidate();
+    }
+    histogram.update(&app.plot_data_per_channel, app.x_axis_mode, app.wave_visible_x);
+
+    let Some(result) = &histogram.result else {
+        ui.label("没有数据");
+        return;
+    };
+    let stats = &result.stats;
+    ui.horizontal_wrapped(|ui| {
+        ui.monospace(format!("点数: {}", stats.count));
+        ui.separator();
+        ui.monospace(format!("平均: {:.4}", stats.mean));
+        ui.separator();
+        ui.monospace(format!("σ: {:.4}", stats.std_dev));
+        ui.separator();
+        ui.monospace(format!("范围: {:.4} ~ {:.4}", stats.min, stats.max));
+    });
+
+    let color = app.plot_data_per_channel.get(&histogram.channel).map_or(egui::Color32::GRAY, |c| c.color);
+    let bars: Vec<Bar> = result.bins.iter().map(|[center, count]| Bar::new(*center, *count).width(result.bin_width)).collect();
+    // 正态分布曲线按点数和区间宽度换算为计数
+    let normal: Vec<[f64; 2]> = if stats.std_dev > 0.0 {
+        let scale = stats.count as f64 * result.bin_width / (stats.std_dev * (2.0 * std::f64::consts::PI).sqrt());
+        (0..=200)
+            .map(|i| {
+                let x = stats.mean - 4.0 * stats.std_dev + 8.0 * stats.std_dev * i as f64 / 200.0;
+                [x, scale * (-0.5 * ((x - stats.mean) / stats.std_dev).powi(2)).exp()]
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    Plot::new("wave_histogram_plot")
+        .label_formatter(|_name, value| format!("{:.4}\n计数: {:.0}", value.x, value.y))
+        .show(ui, |plot_ui| {
+            plot_ui.bar_chart(BarChart::new(bars).color(color));
+            plot_ui.line(Line::new(PlotPoints::new(normal)).color(egui::Color32::GRAY).name("正态分布"));
+            plot_ui.vline(VLine::new(stats.mean).color(egui::Color32::RED).name("平均"));
+            for x in [stats.mean - stats.std_dev, stats.mean + stats.std_dev] {
+                plot_ui.vline(VLine::new(x).color(egui::Color32::RED).style(LineStyle::dashed_dense()).name("±σ"));
+            }
+        });
+}
+
+// 统计表：每个显示通道一行，统计可见窗口或全部历史
+fn render_statistics(app: &mut SerialAssistant, ui: &mut egui::Ui) {
+    ui.horizontal(|ui| {
+        ui.label("统计范围:");
+        let full_history = app.statistics.full_history;
+        ui.radio_value(&mut app.statistics.full_history, false, "可见窗口");
+        ui.radio_value(&mut app.statistics.full_history, true, "全部历史");
+        if app.statistics.full_history != full_history {
+            app.statistics.invalidate();
+        }
+    });
+    app.statistics.update(&app.plot_data_per_channel, app.x_axis_mode, app.wave_visible_x);
+
+    egui::ScrollArea::both().id_salt("wave_stats_table").show(ui, |ui| {
+        egui::Grid::new("wave_stats_grid").striped(true).show(ui, |ui| {
+            for title in ["通道", "点数", "当前", "最小", "最大", "平均", "RMS", "标准差", "峰峰值", "采样率"] {
+                ui.strong(title);
+            }
+            ui.end_row();
+
+            for (index, stats) in &app.statistics.results {
+                let Some(channel) = app.plot_data_per_channel.get(index) else {
+                    continue;
+                };
+                ui.colored_label(channel.color, channel.label());
+                ui.monospace(format!("{}", stats.count));
+                for value in [stats.current, stats.min, stats.max, stats.mean, stats.rms, stats.std_dev, stats.peak_to_peak] {
+                    ui.monospace(format!("{:.4}", value));
+                }
+                match stats.sample_rate {
+                    Some(rate) => ui.monospace(format!("{:.1} Hz", rate)),
+                    None => ui.monospace("-"),
+                };
+                ui.end_row();
+            }
+        });
+    });
+}
+
+// XY 图设置：X、Y 通道和显示的点数
+fn xy_controls(app: &mut SerialAssistant, ui: &mut egui::Ui) {
+    let xy = &mut app.xy;
+    for (label, selected) in [("X", &mut xy.x_channel), ("Y", &mut xy.y_channel)] {
+        let name = app
+            .plot_data_per_channel
+            .get(selected)
+            .map_or_else(|| format!("通道 {}", selected), |c| c.display_name().to_string());
+        egui::ComboBox::from_label(label)
+            .selected_text(name)
+            .show_ui(ui, |ui| {
+                for (index, channel) in &app.plot_data_per_channel {
+                    ui.selectable_value(selected, *index, channel.display_name());
+                }
+            });
+    }
+    ui.label("点数:");
+    ui.add(egui::DragValue::new(&mut xy.points).range(2..=1_000_000));
+    ui.checkbox(&mut xy.equal_aspect, "等比例");
+}
+
+// 触发设置：模式、触发源、边沿、电平、预触发比例和窗口宽度
+fn trigger_controls(app: &mut SerialAssistant, ui: &mut egui::Ui) {
+    let trigger = &mut app.trigger;
+    let before = (trigger.mode, trigger.source, trigger.edge);
+
+    egui::ComboBox::from_label("触发")
+        .selected_text(trigger.mode.label())
+        .show_ui(ui, |ui| {
+            for mode in TriggerMode::ALL {
+                ui.selectable_value(&mut trigger.mode, mode, mode.label());
+            }
+        });
+
+    if trigger.is_active() {
+        let source_name = app
+            .plot_data_per_channel
+            .get(&trigger.source)
+            .map_or_else(|| format!("通道 {}", trigger.source), |c| c.display_name().to_string());
+        egui::ComboBox::from_label("触发源")
+            .selected_text(source_name)
+            .show_ui(ui, |ui| {
+                for (index, channel) in &app.plot_data_per_channel {
+                    ui.selectable_value(&mut trigger.source, *index, channel.display_name());
+                }
+            });
+
+        egui::ComboBox::from_id_salt("trigger_edge")
+            .selected_text(trigger.edge.label())
+            .show_ui(ui, |ui| {
+                for edge in [TriggerEdge::Rising, TriggerEdge::Falling] {
+                    ui.selectable_value(&mut trigger.edge, edge, edge.label());
+                }
+            });
+
+        ui.label("电平:");
+        ui.add(egui::DragValue::new(&mut trigger.level).speed(0.01));
+        ui.label("预触发:");
+        ui.add(egui::Slider::new(&mut trigger.pre_trigger, 0.0..=100.0).suffix("%"));
+        ui.label("窗口:");
+        let suffix = if app.x_axis_mode == XAxisMode::Index { " 点" } else { " s" };
+        let speed = trigger.window * 0.01;
+        ui.add(
+            egui::DragValue::new(&mut trigger.window)
+                .speed(speed)
+                .range(1e-3..=f64::MAX)
+                .suffix(suffix),
+        );
+
+        ui.label(trigger.status_text());
+        if trigger.mode == TriggerMode::Single && ui.button("重新触发").clicked() {
+            trigger.rearm();
+        }
+    }
+
+    if (trigger.mode, trigger.source, trigger.edge) != before {
+        trigger.rearm();
+    }
+}
+
+// 通道列表：显示/隐藏、颜色、重命名，修改后保存到配置文件
+fn render_channel_panel(app: &mut SerialAssistant, ui: &mut egui::Ui) {
+    ui.heading("通道");
+    ui.separator();
+
+    let mut settings_changed = false;
+    let mut clear_all = false;
+    let mut cleared = false;
+    let mut to_remove = None;
+
+    egui::ScrollArea::vertical()
+        .id_salt("wave_channel_list")
+        .show(ui, |ui| {
+            if app.plot_data_per_channel.is_empty() {
+                ui.label("暂无通道数据");
+            }
+
+            for (index, channel) in app.plot_data_per_channel.iter_mut() {
+                ui.push_id(*index, |ui| {
+                    ui.horizontal(|ui| {
+                        settings_changed |= ui.checkbox(&mut channel.visible, "").changed();
+                        settings_changed |= ui.color_edit_button_srgba(&mut channel.color).changed();
+                        ui.label(format!("{}", index));
+
+                        let mut name = channel.display_name().to_string();
+                        let response = ui.add(egui::TextEdit::singleline(&mut name).desired_width(90.0));
+                        if response.changed() {
+                            // 清空名称时恢复脚本提供的名称
+                            channel.user_name = if name.is_empty() { None } else { Some(name) };
+                            settings_changed = true;
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("位数:");
+                        let response = ui.add(egui::DragValue::new(&mut channel.bit_count).range(0..=MAX_BIT_COUNT))
+                            .on_hover_text("大于 0 时按位显示为数字通道");
+                        settings_changed |= response.changed();
+                    });
+                    if channel.is_logic() {
+                        egui::CollapsingHeader::new("位名称").id_salt("bit_names").show(ui, |ui| {
+                            channel.bit_names.resize(channel.bit_count, String::new());
+                            for (bit, name) in channel.bit_names.iter_mut().enumerate() {
+                                ui.horizontal(|ui| {
+                                    ui.label(format!("{}", bit));
+                                    let edit = egui::TextEdit::singleline(name).desired_width(100.0).hint_text(format!("位 {}", bit));
+                                    settings_changed |= ui.add(edit).changed();
+                                });
+                            }
+                        });
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("增益:");
+                        settings_changed |= ui.add(egui::DragValue::new(&mut channel.gain).speed(0.01)).changed();
+                        ui.label("偏移:");
+                        settings_changed |= ui.add(egui::DragValue::new(&mut channel.offset).speed(0.01)).changed();
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{} 点", channel.data.len()));
+                        if let Some(sample) = channel.data.last() {
+                            ui.monospace(format!("{:.4} {}", sample.y, channel.unit));
+                        }
+                        if ui.small_button("清空").clicked() {
+                            channel.clear();
+                            cleared = true;
+                        }
+                        if ui.small_button("删除").clicked() {
+                            to_remove = Some(*index);
+                        }
+                    });
+                });
+                ui.separator();
+            }
+
+            if !app.plot_data_per_channel.is_empty() && ui.button("清空全部数据").clicked() {
+                clear_all = true;
+            }
+        });
+
+    if let Some(index) = to_remove {
+        app.plot_data_per_channel.remove(&index);
+        settings_changed = true;
+    }
+    if clear_all {
+        app.clear_plot_data();
+    }
+    // 清空数据后派生通道的滤波器从头计算
+    if cleared {
+        app.derived.reset();
+    }
+    if settings_changed {
+        app.save_channel_settings();
+    }
+}
+
+fn wave_viewport_content(app: &mut SerialAssistant, ui: &mut egui::Ui, _ctx: &egui::Context) {    
+    // 最近一帧解析结果中的命名字段
+    if !app.frame_fields.is_empty() {
+        ui.horizontal_wrapped(|ui| {
+            for (name, value) in &app.frame_fields {
+                ui.label(format!("{}:", name));
+                ui.monospace(value);
+                ui.separator();
+            }
+        });
+    }
+
+    if app.plot_mode == PlotMode::Xy {
+        xy_plot(app, ui);
+    } else {
+        time_series_plot(app, ui);
+    }
+    app.wave_plot_rect = Some(ui.min_rect());
+
+    // 处理关闭请求
+    if ui.input(|i| i.viewport().close_requested()) {
+        app.plot_visible = false;
+    }
+
+     // 捕获鼠标位置
+     if let Some(pointer_pos) = ui.input(|i| i.pointer.hover_pos()) {
+        app.pointer_pos = pointer_pos;
+    }
+}
+
+
+// 时域波形：横轴为采样序号或时间，支持触发、暂停、时间轴和光标
+fn time_series_plot(app: &mut SerialAssistant, ui: &mut egui::Ui) {
+    // 确保宽度不为负值
+    let width = ui.available_width().max(300.0);
+    let x_mode = app.x_axis_mode;
+    let epoch = app.capture_epoch;
+    let buckets = (width as usize).max(100);
+
+    // 触发模式下横轴是相对触发点的偏移，绝对时间按秒显示
+    let triggered = app.trigger.is_active();
+    let format_mode = if triggered && x_mode == XAxisMode::Absolute { XAxisMode::Relative } else { x_mode };
+    let mut reset_view = false;
+    if triggered {
+        if !app.wave_paused {
+            app.trigger.update(&app.plot_data_per_channel, x_mode, buckets);
+        }
+        reset_view = app.trigger.take_new_capture();
+        if let Some(capture) = &app.trigger.capture {
+            let time = epoch + chrono::Duration::microseconds((capture.trigger_t * 1e6) as i64);
+            ui.label(format!("触发时刻: {}", time.format("%H:%M:%S%.3f")));
+        }
+    }
+
+    // 光标读数，时间模式下 1/Δx 即频率
+    if app.cursors.enabled {
+        let cursors = &app.cursors;
+        let dx = cursors.x[1] - cursors.x[0];
+        let dy = cursors.y[1] - cursors.y[0];
+        let unit = if x_mode == XAxisMode::Index { "" } else { " s" };
+        let freq_unit = if x_mode == XAxisMode::Index { "" } else { " Hz" };
+        ui.horizontal_wrapped(|ui| {
+            ui.monospace(format!("X1: {}  X2: {}", format_x(cursors.x[0], format_mode, epoch), format_x(cursors.x[1], format_mode, epoch)));
+            ui.separator();
+            ui.monospace(format!("Δx: {:.6}{}", dx, unit));
+            ui.separator();
+            if dx != 0.0 {
+                ui.monospace(format!("1/Δx: {:.4}{}", 1.0 / dx.abs(), freq_unit));
+            } else {
+                ui.monospace("1/Δx: -");
+            }
+            ui.separator();
+            ui.monospace(format!("Y1: {:.4}  Y2: {:.4}  Δy: {:.4}", cursors.y[0], cursors.y[1], dy));
+        });
+    }
+
+    // 所有显示通道的数据范围，用于时间轴
+    let extent = app
+        .plot_data_per_channel
+        .values()
+        .filter(|c| c.visible)
+        .filter_map(|c| Some((c.data.first()?.x_on(x_mode), c.data.last()?.x_on(x_mode))))
+        .reduce(|a, b| (a.0.min(b.0), a.1.max(b.1)));
+    let paused = app.wave_paused;
+    let scroll = app.wave_scroll.take();
+    let go_live = std::mem::take(&mut app.wave_go_live);
+
+    // 确保高度不为负值，并留出一行给时间轴
+    let height = (ui.available_height() - 30.0).max(200.0);
+
+    // 数字通道显示在下方单独的图中，和模拟波形共用横轴
+    let logic_lanes: usize = app
+        .plot_data_per_channel
+        .values()
+        .filter(|c| c.visible && c.is_logic())
+        .map(|c| c.bit_count)
+        .sum();
+    let logic_height = if logic_lanes > 0 { (logic_lanes as f32 * 20.0 + 30.0).min(height * 0.5) } else { 0.0 };
+    let height = height - logic_height;
+    let link_group = if triggered { "serial_wave_link_trigger" } else { "serial_wave_link" };
+
+    // 分道显示时每个通道占一道，各自归一化
+    let stacked = app.stacked_lanes;
+    let lane_count = app.plot_data_per_channel.values().filter(|c| c.visible && !c.is_logic()).count();
+    let scales: TraceScales = Default::default();
+    let hover_scales = scales.clone();
+
+    // 绘制波形，触发视图使用单独的缩放状态
+    let plot_id = if triggered { "serial_wave_plot_trigger" } else { "serial_wave_plot_area" };
+    let plot = Plot::new("serial_wave_plot")
+    .id(egui::Id::new(plot_id))
+    .x_axis_formatter(move |mark, _range| format_x(mark.value, format_mode, epoch))
+    .label_formatter(move |name, value| {
+        let x = format_x(value.x, format_mode, epoch);
+        if name.is_empty() {
+            format!("x: {}\ny: {:.4}", x, value.y)
+        } else {
+            // 悬停时显示通道的原始数值
+            let y = hover_scales.borrow().by_name(name).map_or(value.y, |scale| scale.invert(value.y));
+            format!("{}\nx: {}\ny: {:.4}", name, x, y)
+        }
+    })
+    .view_aspect(2.0)
+    .set_margin_fraction(egui::vec2(0.05, 0.1))  // 上下留出边距，使显示更美观
+    .show_axes([true, !stacked])
+    .show_grid([true, !stacked])
+    .legend(Legend::default())
+    .allow_zoom(true)
+    .allow_drag(!app.cursors.wants_pointer())
+    .link_axis(link_group, [true, false])
+    .height(height)
+    .width(width-20.0)
+    .show(ui, |plot_ui| {
+        let bounds = plot_ui.plot_bounds();
+        app.cursors.show(plot_ui);
+        if triggered {
+            if reset_view {
+                plot_ui.set_auto_bounds(true);
+            }
+            if let Some(capture) = &app.trigger.capture {
+                let visible = capture
+                    .traces
+                    .iter()
+                    .filter_map(|(index, points)| {
+                        let channel = app.plot_data_per_channel.get(index).filter(|c| c.visible && !c.is_logic())?;
+                        Some((*index, channel, points))
+                    });
+                for (lane, (index, channel, points)) in visible.enumerate() {
+                    let lane = stacked.then(|| lane_count.saturating_sub(lane + 1));
+                    draw_trace(plot_ui, index, channel, points.clone(), lane, &scales);
+                }
+            }
+            draw_lane_separators(plot_ui, stacked, lane_count);
+
+            // 触发点和触发电平，电平按触发源的显示变换绘制
+            plot_ui.vline(VLine::new(0.0).color(egui::Color32::GRAY).style(LineStyle::dashed_dense()));
+            if let Some(source) = app.plot_data_per_channel.get(&app.trigger.source) {
+                let level = scales.borrow().display(app.trigger.source, source, app.trigger.level);
+                plot_ui.hline(HLine::new(level).color(source.color).style(LineStyle::dashed_loose()));
+            }
+            let capture = app.trigger.capture.as_ref()?;
+            return Some([bounds.min()[0] + capture.trigger_x, bounds.max()[0] + capture.trigger_x]);
+        }
+
+        // 暂停时固定横轴范围，纵轴仍按可见数据自动缩放
+        if go_live {
+            plot_ui.set_auto_bounds(true);
+        } else if paused {
+            let view = match scroll {
+                Some(start) => Some([start, start + bounds.width()]),
+                None if plot_ui.auto_bounds().x => {
+                    let newest = extent.map_or(bounds.max()[0], |e| e.1);
+                    Some([bounds.min()[0], bounds.max()[0].min(newest)])
+                }
+                None => None,
+            };
+            if let Some([min_x, max_x]) = view {
+                plot_ui.set_plot_bounds(PlotBounds::from_min_max([min_x, bounds.min()[1]], [max_x, bounds.max()[1]]));
+                plot_ui.set_auto_bounds(egui::Vec2b::new(false, true));
+            }
+        }
+
+        // 自动缩放时绘制全部历史(使用缓存的分段最值，不遍历数据)，否则只绘制可见范围；每个像素列最多两个点
+        let follow_x = plot_ui.auto_bounds().x && !paused;
+
+        for (lane, (index, channel)) in app.plot_data_per_channel.iter().filter(|(_, c)| c.visible && !c.is_logic()).enumerate() {
+            if !channel.data.is_empty() {
+                let range = if follow_x {
+                    0..channel.data.len()
+                } else {
+                    channel.data.index_range(bounds.min()[0], bounds.max()[0], x_mode)
+                };
+                let points = channel.data.decimate(range, buckets, x_mode);
+                let lane = stacked.then(|| lane_count - lane - 1);
+                draw_trace(plot_ui, *index, channel, points, lane, &scales);
+            }
+        }
+        draw_lane_separators(plot_ui, stacked, lane_count);
+        draw_references(plot_ui, app, x_mode, stacked, &scales, buckets);
+        if app.alarms.show_markers {
+            draw_alarms(plot_ui, app, x_mode, stacked, &scales);
+        }
+        Some([bounds.min()[0], bounds.max()[0]])
+    });
+
+    app.wave_visible_x = plot.inner;
+    // 触发视图的横坐标是相对触发点的偏移，换算回原始横坐标
+    let x_offset = app.trigger.capture.as_ref().filter(|_| triggered).map_or(0.0, |c| c.trigger_x);
+    if logic_lanes > 0 {
+        logic_plot(app, ui, logic_height, width, link_group, x_offset, format_mode);
+    }
+    app.plot_hover = plot.response.hover_pos().map(|pos| {
+        let value = plot.transform.value_from_position(pos);
+        [value.x + x_offset, value.y]
+    });
+
+    // 时间轴：拖动查看历史数据，拖动时自动暂停
+    if let (false, Some(view), Some((first, last))) = (triggered, plot.inner, extent) {
+        let view_width = view[1] - view[0];
+        let mut start = view[0];
+        ui.horizontal(|ui| {
+            ui.label("时间轴:");
+            ui.spacing_mut().slider_width = (width - 200.0).max(100.0);
+            let slider = egui::Slider::new(&mut start, first..=(last - view_width).max(first))
+                .show_value(false);
+            if ui.add(slider).changed() {
+                app.wave_paused = true;
+                app.wave_scroll = Some(start);
+            }
+            ui.monospace(format_x(view[0], x_mode, epoch));
+        });
+    }
+}
+
+// 参考波形半透明叠加在实时波形上，差值为实时通道减去同一横坐标处的参考值
+fn draw_references(plot_ui: &mut PlotUi, app: &SerialAssistant, x_mode: XAxisMode, stacked: bool, scales: &TraceScales, buckets: usize) {
+    let bounds = plot_ui.plot_bounds();
+    let (min_x, max_x) = (bounds.min()[0], bounds.max()[0]);
+    let scales = scales.borrow();
+
+    for reference in app.references.references.iter().filter(|r| r.visible) {
+        let offset = reference.offset(x_mode);
+        for trace in &reference.traces {
+            let channel = app.plot_data_per_channel.get(&trace.channel);
+            let display = |y: f64| match channel {
+                Some(channel) => scales.display(trace.channel, channel, y),
+                None => y,
+            };
+
+            let start = trace.samples.partition_point(|s| s.x_on(x_mode) + offset < min_x).saturating_sub(1);
+            let end = (trace.samples.partition_point(|s| s.x_on(x_mode) + offset <= max_x) + 1).min(trace.samples.len());
+            let step = ((end.saturating_sub(start)) / (buckets * 2)).max(1);
+            let points: Vec<[f64; 2]> = trace.samples[start..end]
+                .iter()
+                .step_by(step)
+                .map(|s| [s.x_on(x_mode) + offset, display(s.y)])
+                .collect();
+            let color = channel.map_or(trace.color, |c| c.color).gamma_multiply(0.4);
+            plot_ui.line(
+                Line::new(PlotPoints::new(points))
+                    .color(color)
+                    .width(2.0)
+                    .name(format!("{}: {}", reference.name, trace.label)),
+            );
+
+            // 分道显示时各道纵轴不同，不绘制差值
+            if let (true, false, Some(channel)) = (reference.show_difference, stacked, channel) {
+                let range = channel.data.index_range(min_x, max_x, x_mode);
+                let difference: Vec<[f64; 2]> = channel
+                    .data
+                    .decimate(range, buckets, x_mode)
+                    .into_iter()
+                    .filter_map(|[x, y]| {
+                        let reference_y = trace.value_at(x - offset, x_mode)?;
+                        Some([x, channel.scaled(y) - channel.scaled(reference_y)])
+                    })
+                    .collect();
+                plot_ui.line(
+                    Line::new(PlotPoints::new(difference))
+                        .color(channel.color)
+                        .style(LineStyle::dashed_dense())
+                        .name(format!("Δ {} - {}", channel.plot_label(), reference.name)),
+                );
+            }
+        }
+    }
+}
+
+// 参考波形窗口：快照当前数据或从文件加载，调整位置和差值显示
+fn render_reference_window(app: &mut SerialAssistant, ui: &mut egui::Ui) {
+    let x_mode = app.x_axis_mode;
+    ui.horizontal(|ui| {
+        ui.label("名称:");
+        ui.add(egui::TextEdit::singleline(&mut app.references.name).desired_width(120.0));
+        if ui.button("快照当前视图").on_hover_text("保存显示的模拟通道在当前视图中的数据").clicked() {
+            let name = app.references.name.trim().to_string();
+            match Reference::snapshot(name, &app.plot_data_per_channel, app.wave_visible_x, x_mode) {
+                Some(reference) => {
+                    app.references.references.push(reference);
+                    app.references.name = format!("参考 {}", app.references.references.len() + 1);
+                }
+                None => app.file_message = Some("快照失败: 当前视图中没有数据".to_string()),
+            }
+        }
+        if ui.button("从文件加载").clicked()
+            && let Some(path) = rfd::FileDialog::new().add_filter("CSV", &["csv"]).pick_file()
+        {
+            let name = path.file_stem().map_or_else(|| "参考".to_string(), |s| s.to_string_lossy().into_owned());
+            match crate::import::read_csv(&path).map(|csv| Reference::from_csv(name, &csv)) {
+                Ok(Some(reference)) => {
+                    app.file_message = Some(format!("已加载参考波形 {}", reference.name));
+                    app.references.references.push(reference);
+                }
+                Ok(None) => app.file_message = Some("加载参考波形失败: 文件中没有数据".to_string()),
+                Err(e) => app.file_message = Some(format!("加载参考波形失败: {}", e)),
+            }
+        }
+    });
+    ui.separator();
+
+    if app.references.references.is_empty() {
+        ui.label("暂无参考波形");
+        return;
+    }
+
+    let view_start = app.wave_visible_x.map(|[min_x, _]| min_x);
+    let epoch = app.capture_epoch;
+    let mut to_remove = None;
+    let mut message = None;
+    for (i, reference) in app.references.references.iter_mut().enumerate() {
+        ui.push_id(i, |ui| {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut reference.visible, "");
+                ui.add(egui::TextEdit::singleline(&mut reference.name).desired_width(100.0));
+                ui.checkbox(&mut reference.show_difference, "差值");
+                ui.label(format!("{} 个通道", reference.traces.len()));
+            });
+            ui.horizontal(|ui| {
+                let unit = if x_mode == XAxisMode::Index { "" } else { " s" };
+                ui.label("起点:");
+                ui.add(egui::DragValue::new(reference.offset_mut(x_mode)).speed(0.01).suffix(unit));
+                if let Some(start) = view_start
+                    && ui.small_button("对齐到视图起点").clicked()
+                {
+                    *reference.offset_mut(x_mode) = start;
+                }
+                if ui.small_button("保存").clicked()
+                    && let Some(path) = rfd::FileDialog::new()
+                        .add_filter("CSV", &["csv"])
+                        .set_file_name(format!("{}.csv", reference.name))
+                        .save_file()
+                {
+                    message = Some(match reference.save_csv(&path, epoch) {
+                        Ok(rows) => format!("已保存 {} 行到 {}", rows, path.display()),
+                        Err(e) => format!("保存参考波形失败: {}", e),
+                    });
+                }
+                if ui.small_button("删除").clicked() {
+                    to_remove = Some(i);
+                }
+            });
+        });
+        ui.separator();
+    }
+    if let Some(i) = to_remove {
+        app.references.references.remove(i);
+    }
+    if message.is_some() {
+        app.file_message = message;
+    }
+}
+
+// 报警上下限画成虚线，越限事件画成标记点，都按通道的显示变换绘制
+fn draw_alarms(plot_ui: &mut PlotUi, app: &SerialAssistant, x_mode: XAxisMode, stacked: bool, scales: &TraceScales) {
+    let bounds = plot_ui.plot_bounds();
+    let scales = scales.borrow();
+    let visible = |index: &usize| app.plot_data_per_channel.get(index).filter(|c| c.visible && !c.is_logic());
+
+    // 分道显示时各道的纵轴不同，上下限线只在叠加显示时绘制
+    if !stacked {
+        for (index, alarm) in app.alarms.limits.iter().filter(|(_, a)| a.enabled) {
+            let Some(channel) = visible(index) else {
+                continue;
+            };
+            for (enabled, limit) in [(alarm.high_enabled, alarm.high), (alarm.low_enabled, alarm.low)] {
+                if enabled {
+                    plot_ui.hline(HLine::new(scales.display(*index, channel, limit)).color(channel.color).style(LineStyle::dashed_loose()).width(1.0));
+                }
+            }
+        }
+    }
+
+    let mut markers: HashMap<usize, Vec<[f64; 2]>> = HashMap::new();
+    for event in app.alarms.events.iter().filter(|e| e.level != AlarmLevel::Normal) {
+        let x = event.sample.x_on(x_mode);
+        if x < bounds.min()[0] || x > bounds.max()[0] {
+            continue;
+        }
+        if let Some(channel) = visible(&event.channel) {
+            markers.entry(event.channel).or_default().push([x, scales.display(event.channel, channel, event.sample.y)]);
+        }
+    }
+    for (_, points) in markers {
+        plot_ui.points(Points::new(points).color(egui::Color32::RED).radius(4.0).shape(egui_plot::MarkerShape::Diamond));
+    }
+}
+
+// 报警窗口：每个通道的上下限和动作设置，以及越限事件记录
+fn render_alarm_window(app: &mut SerialAssistant, ui: &mut egui::Ui) {
+    let mut changed = false;
+    let editing = app.alarms.editing_channel;
+    let names: std::collections::BTreeMap<usize, String> = app.plot_data_per_channel.iter().map(|(i, c)| (*i, c.display_name().to_string())).collect();
+    let channel_name = |index: usize| names.get(&index).cloned().unwrap_or_else(|| format!("通道{}", index));
+
+    ui.horizontal(|ui| {
+        ui.label("通道:");
+        egui::ComboBox::from_id_salt("alarm_channel")
+            .selected_text(channel_name(editing))
+            .show_ui(ui, |ui| {
+                for (index, name) in &names {
+                    ui.selectable_value(&mut app.alarms.editing_channel, *index, name);
+                }
+            });
+        match app.alarms.limits.entry(editing) {
+            Entry::Occupied(entry) => {
+                if ui.button("删除报警").clicked() {
+                    entry.remove();
+                    changed = true;
+                }
+            }
+            Entry::Vacant(entry) => {
+                if ui.button("添加报警").clicked() {
+                    entry.insert(ChannelAlarm::default());
+                    changed = true;
+                }
+            }
+        }
+    });
+
+    if let Some(alarm) = app.alarms.limits.get_mut(&editing) {
+        let before = alarm.clone();
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut alarm.enabled, "启用");
+            ui.checkbox(&mut alarm.high_enabled, "上限:");
+            ui.add_enabled(alarm.high_enabled, egui::DragValue::new(&mut alarm.high).speed(0.01));
+            ui.checkbox(&mut alarm.low_enabled, "下限:");
+            ui.add_enabled(alarm.low_enabled, egui::DragValue::new(&mut alarm.low).speed(0.01));
+        });
+        ui.label("上下限按通道的原始数值判断, 不受显示增益和偏移影响");
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut alarm.beep, "提示音");
+            ui.checkbox(&mut alarm.flash, "闪烁窗口");
+            egui::ComboBox::from_label("动作")
+                .selected_text(alarm.action.label())
+                .show_ui(ui, |ui| {
+                    for action in AlarmAction::ALL {
+                        ui.selectable_value(&mut alarm.action, action, action.label());
+                    }
+                });
+        });
+        if alarm.action == AlarmAction::SendCommand {
+            ui.horizontal(|ui| {
+                ui.label("命令:");
+                ui.add(egui::TextEdit::singleline(&mut alarm.command).desired_width(260.0));
+                ui.checkbox(&mut alarm.command_hex, "HEX");
+            });
+        }
+        if *alarm != before {
+            app.alarms.reset_state(editing);
+            changed = true;
+        }
+    }
+    if changed {
+        app.save_alarm_settings();
+    }
+
+    ui.separator();
+    ui.horizontal(|ui| {
+        ui.strong(format!("事件记录 ({})", app.alarms.events.len()));
+        ui.checkbox(&mut app.alarms.show_markers, "在波形中标记");
+        if ui.button("清空").clicked() {
+            app.alarms.events.clear();
+        }
+        if ui.button("导出").clicked()
+            && let Some(path) = rfd::FileDialog::new().add_filter("CSV", &["csv"]).set_file_name("alarms.csv").save_file()
+        {
+            app.file_message = Some(match crate::alarm::export_events(&path, &app.alarms.events, channel_name) {
+                Ok(()) => format!("已导出 {} 条报警记录到 {}", app.alarms.events.len(), path.display()),
+                Err(e) => format!("导出报警记录失败: {}", e),
+            });
+        }
+    });
+
+    let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+    let events = &app.alarms.events;
+    egui::ScrollArea::vertical()
+        .max_height(240.0)
+        .stick_to_bottom(true)
+        .auto_shrink([false, true])
+        .show_rows(ui, row_height, events.len(), |ui, rows| {
+            for event in events.range(rows) {
+                let color = if event.level == AlarmLevel::Normal { egui::Color32::GRAY } else { egui::Color32::RED };
+                ui.horizontal(|ui| {
+                    ui.monospace(event.time.format("%Y-%m-%d %H:%M:%S%.3f").to_string());
+                    ui.monospace(channel_name(event.channel));
+                    ui.colored_label(color, event.level.label());
+                    ui.monospace(format!("{:.4}", event.sample.y));
+                });
+            }
+        });
+}
+
+// 通道在图中的纵向变换：先应用增益和偏移，分道显示时再归一化到所在的道
+#[derive(Clone, Copy)]
+struct TraceScale {
+    gain: f64,
+    offset: f64,
+    lane: Option<(f64, f64, f64)>, // 道的底部、数据最小值、数据范围
+}
+
+impl TraceScale {
+    fn apply(&self, y: f64) -> f64 {
+        let y = y * self.gain + self.offset;
+        match self.lane {
+            Some((base, min, span)) => base + 0.1 + 0.8 * (y - min) / span,
+            None => y,
+        }
+    }
+
+    fn invert(&self, y: f64) -> f64 {
+        let y = match self.lane {
+            Some((base, min, span)) => min + (y - base - 0.1) / 0.8 * span,
+            None => y,
+        };
+        if self.gain == 0.0 { y } else { (y - self.offset) / self.gain }
+    }
+}
+
+// 按通道号记录每条曲线的变换，悬停提示按曲线名称找到通道号，换算回原始数值
+#[derive(Default)]
+struct TraceScaleMap {
+    channels: HashMap<usize, TraceScale>,
+    names: HashMap<String, usize>,
+}
+
+impl TraceScaleMap {
+    fn by_name(&self, name: &str) -> Option<&TraceScale> {
+        self.channels.get(self.names.get(name)?)
+    }
+
+    // 通道数值在图中的纵坐标，还没有绘制的通道只应用增益和偏移
+    fn display(&self, index: usize, channel: &PlotChannel, y: f64) -> f64 {
+        self.channels.get(&index).map_or(channel.scaled(y), |scale| scale.apply(y))
+    }
+}
+
+type TraceScales = Rc<RefCell<TraceScaleMap>>;
+
+fn draw_trace(plot_ui: &mut PlotUi, index: usize, channel: &PlotChannel, mut points: Vec<[f64; 2]>, lane: Option<usize>, scales: &TraceScales) {
+    // 重名的通道在名称后附加通道号，图例和悬停提示才能区分
+    let mut name = channel.plot_label();
+    if scales.borrow().names.get(&name).is_some_and(|other| *other != index) {
+        name = format!("{} [ch{}]", name, index);
+    }
+    let mut scale = TraceScale {
+        gain: channel.gain,
+        offset: channel.offset,
+        lane: None,
+    };
+    if let Some(lane) = lane {
+        let (min, max) = points
+            .iter()
+            .map(|p| channel.scaled(p[1]))
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), y| (min.min(y), max.max(y)));
+        let span = if max > min { max - min } else { 1.0 };
+        let min = if max > min { min } else { min - 0.5 };
+        scale.lane = Some((lane as f64, min, span));
+
+        // 道的左上角显示通道名称
+        let left = plot_ui.plot_bounds().min()[0];
+        let label = Text::new(PlotPoint::new(left, lane as f64 + 1.0), name.clone())
+            .color(channel.color)
+            .anchor(egui::Align2::LEFT_TOP);
+        plot_ui.text(label);
+    }
+
+    for point in &mut points {
+        point[1] = scale.apply(point[1]);
+    }
+    let mut scales = scales.borrow_mut();
+    scales.channels.insert(index, scale);
+    scales.names.insert(name.clone(), index);
+    plot_ui.line(Line::new(PlotPoints::new(points)).color(channel.color).name(name).width(2.0));
+}
+
+// 数字通道：每一位画成一道高低电平，只在电平变化处产生折点
+fn logic_plot(app: &SerialAssistant, ui: &mut egui::Ui, height: f32, width: f32, link_group: &'static str, x_shift: f64, format_mode: XAxisMode) {
+    let x_mode = app.x_axis_mode;
+    let epoch = app.capture_epoch;
+    let channels: Vec<&PlotChannel> = app.plot_data_per_channel.values().filter(|c| c.visible && c.is_logic()).collect();
+    let lane_total: usize = channels.iter().map(|c| c.bit_count).sum();
+
+    Plot::new("serial_wave_plot_logic")
+        .id(egui::Id::new(format!("{}_logic", link_group)))
+        .link_axis(link_group, [true, false])
+        .x_axis_formatter(move |mark, _range| format_x(mark.value, format_mode, epoch))
+        .label_formatter(move |name, value| format!("{}\nx: {}", name, format_x(value.x, format_mode, epoch)))
+        .show_axes([true, false])
+        .show_grid([true, false])
+        .include_y(0.0)
+        .include_y(lane_total as f64)
+        .allow_zoom([true, false])
+        .allow_drag([true, false])
+        .height(height)
+        .width(width - 20.0)
+        .show(ui, |plot_ui| {
+            let bounds = plot_ui.plot_bounds();
+            let mut lane = lane_total;
+            for channel in channels {
+                let range = channel.data.index_range(bounds.min()[0] + x_shift, bounds.max()[0] + x_shift, x_mode);
+                let bits = channel.bit_count;
+                let first_lane = lane.saturating_sub(bits);
+                let level = |bit: usize, high: bool| (first_lane + bits - 1 - bit) as f64 + if high { 0.8 } else { 0.1 };
+
+                let mut steps: Vec<Vec<[f64; 2]>> = vec![Vec::new(); bits];
+                let mut previous: Vec<Option<bool>> = vec![None; bits];
+                let mut last_x = None;
+                for sample in channel.data.range(range) {
+                    let x = sample.x_on(x_mode) - x_shift;
+                    let value = sample.y as i64;
+                    for bit in 0..bits {
+                        let high = (value >> bit) & 1 == 1;
+                        match previous[bit] {
+                            Some(was_high) if was_high == high => {}
+                            Some(was_high) => {
+                                steps[bit].push([x, level(bit, was_high)]);
+                                steps[bit].push([x, level(bit, high)]);
+                            }
+                            None => steps[bit].push([x, level(bit, high)]),
+                        }
+                        previous[bit] = Some(high);
+                    }
+                    last_x = Some(x);
+                }
+
+                for (bit, mut points) in steps.into_iter().enumerate() {
+                    if let (Some(x), Some(high)) = (last_x, previous[bit]) {
+                        points.push([x, level(bit, high)]);
+                    }
+                    let name = channel.bit_name(bit);
+                    let label = Text::new(PlotPoint::new(bounds.min()[0], level(bit, true) + 0.15), name.clone())
+                        .color(channel.color)
+                        .anchor(egui::Align2::LEFT_TOP);
+                    plot_ui.text(label);
+                    plot_ui.line(Line::new(PlotPoints::new(points)).color(channel.color).name(name).width(1.5));
+                }
+                lane = first_lane;
+            }
+        });
+}
+
+fn draw_lane_separators(plot_ui: &mut PlotUi, stacked: bool, lane_count: usize) {
+    if stacked {
+        for lane in 1..lane_count {
+            plot_ui.hline(HLine::new(lane as f64).color(egui::Color32::DARK_GRAY).width(0.5));
+        }
+    }
+}
+
+// XY 图：两个通道分别作为横轴和纵轴，最新的点用圆点标出
+fn xy_plot(app: &mut SerialAssistant, ui: &mut egui::Ui) {
+    let pairs = app.xy.pairs(&app.plot_data_per_channel);
+    let color = app
+        .plot_data_per_channel
+        .get(&app.xy.y_channel)
+        .map_or(egui::Color32::GRAY, |c| c.color);
+    let axis_name = |index: usize| {
+        app.plot_data_per_channel
+            .get(&index)
+            .map_or_else(|| format!("通道 {}", index), |c| c.label())
+    };
+
+    let mut plot = Plot::new("serial_wave_plot_xy")
+        .x_axis_label(axis_name(app.xy.x_channel))
+        .y_axis_label(axis_name(app.xy.y_channel))
+        .set_margin_fraction(egui::vec2(0.05, 0.1))
+        .show_axes([true, true])
+        .show_grid([true, true])
+        .allow_zoom(true)
+        .allow_drag(!app.cursors.wants_pointer())
+        .height((ui.available_height() - 10.0).max(200.0))
+        .width(ui.available_width().max(300.0) - 20.0);
+    if app.xy.equal_aspect {
+        plot = plot.data_aspect(1.0);
+    }
+
+    plot.show(ui, |plot_ui| {
+        app.cursors.show(plot_ui);
+        if let Some(last) = pairs.last().copied() {
+            plot_ui.line(Line::new(PlotPoints::new(pairs)).color(color).width(1.5));
+            plot_ui.points(Points::new(vec![last]).radius(4.0).color(color));
+        }
+    });
+    // XY 图没有时间轴，统计按全部历史计算
+    app.wave_visible_x = None;
+    app.plot_hover = None;
+}
+
+// 横轴刻度和悬停提示的文本，绝对时间模式下显示为 时:分:秒.毫秒
+pub fn format_x(x: f64, mode: XAxisMode, epoch: DateTime<Local>) -> String {
+    match mode {
+        XAxisMode::Index => format!("{}", x),
+        XAxisMode::Relative => format!("{:.3}s", x),
+        XAxisMode::Absolute => {
+            let time = epoch + chrono::Duration::microseconds((x * 1e6) as i64);
+            time.format("%H:%M:%S%.3f").to_string()
+        }
+    }
+}
+
+// 添加创建图标的函数
+pub fn create_wave_icon() -> IconData {
+    let width = 64;
+    let height = 64;
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    
+    // 创建一个正弦波形图标
+    for y in 0..height {
+        for x in 0..width {//波形周期从 4π
+            let wave = ((x as f32 / width as f32 * 4.0 * std::f32::consts::PI).sin() * 0.5 + 0.5) * height as f32;
+            let is_wave = (y as f32 - wave).abs() < 5.0;//线条宽度从 5.0
+            
+            //纯蓝色波形（RGB: 0,0,255）
+            rgba.extend_from_slice(&[
+                if is_wave { 0 } else { 255 },    // R
+                if is_wave { 0 } else { 255 },    // G
+                255,                              // B
+                255,                              // A
+            ]);
+        }
+    }
+    
+    IconData {
+        rgba,
+        width: width as _,
+        height: height as _,
+    }
+}
@@ -0,0 +1,58 @@
+use crate::channel::PlotChannel;
+use std::collections::BTreeMap;
+
+// 波形显示方式：横轴为序号/时间，或者两个通道组成 XY 图
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PlotMode {
+    TimeSeries,
+    Xy,
+}
+
+impl PlotMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PlotMode::TimeSeries => "时域",
+            PlotMode::Xy => "XY",
+        }
+    }
+}
+
+// XY 图设置：一个通道作为 X，另一个作为 Y
+pub struct XySettings {
+    pub x_channel: usize,
+    pub y_channel: usize,
+    pub points: usize,      // 只显示最近的点数
+    pub equal_aspect: bool, // 两个轴使用相同的比例，圆不会变成椭圆
+}
+
+impl Default for XySettings {
+    fn default() -> Self {
+        Self {
+            x_channel: 0,
+            y_channel: 1,
+            points: 1000,
+            equal_aspect: false,
+        }
+    }
+}
+
+impl XySettings {
+    // 两个通道从最新的点开始按顺序配对，适用于两个通道在同一帧中发送的情况
+    // 配对后的数值已应用各通道的增益和偏移
+    pub fn pairs(&self, channels: &BTreeMap<usize, PlotChannel>) -> Vec<[f64; 2]> {
+        let (Some(x), Some(y)) = (channels.get(&self.x_channel), channels.get(&self.y_channel)) else {
+            return Vec::new();
+        };
+        let count = x.data.len().min(y.data.len()).min(self.points);
+        let mut pairs: Vec<[f64; 2]> = x
+            .data
+            .iter()
+            .rev()
+            .zip(y.data.iter().rev())
+            .take(count)
+            .map(|(x_sample, y_sample)| [x.scaled(x_sample.y), y.scaled(y_sample.y)])
+            .collect();
+        pairs.reverse();
+        pairs
+    }
+}